@@ -0,0 +1,188 @@
+//! Stable URLs for schemes that upstream renamed or removed.
+//!
+//! Aliases are read from the YAML file named by `ALIASES_FILE` (default `aliases.yaml`):
+//!
+//! ```yaml
+//! renamed:
+//!   old-slug: new-slug
+//! removed:
+//!   dropped-slug: closest-successor   # or ~ when there is none
+//! ```
+//!
+//! When the server starts, slugs that disappeared since the previous start are also
+//! recorded in the file named by `SLUG_TRACKING_FILE` (default `.cache/scheme-slugs.json`),
+//! so old links answer 410 Gone instead of fuzzy-redirecting to something unrelated.
+//! Tracked removals have no successor unless the alias file names one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const DEFAULT_TRACKING_FILE: &str = ".cache/scheme-slugs.json";
+
+/// Only set on the server start path, so index loads in tests, `lint` and `check-matrix`
+/// neither read nor rewrite the tracking file.
+static TRACKING_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Track removed slugs on this process's index load. An empty `SLUG_TRACKING_FILE`
+/// turns tracking off.
+pub fn enable_tracking() {
+    let path = std::env::var("SLUG_TRACKING_FILE").unwrap_or_else(|_| DEFAULT_TRACKING_FILE.to_string());
+    if !path.is_empty() {
+        let _ = TRACKING_FILE.set(PathBuf::from(path));
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    renamed: HashMap<String, String>,
+    #[serde(default)]
+    removed: HashMap<String, Option<String>>,
+}
+
+/// Slugs seen on the last load, and every slug that has disappeared since tracking began.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrackedSlugs {
+    known: BTreeSet<String>,
+    removed: BTreeSet<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Alias<'a> {
+    Renamed(&'a str),
+    Removed(Option<&'a str>),
+}
+
+#[derive(Debug, Default)]
+pub struct Aliases {
+    renamed: HashMap<String, String>,
+    removed: HashMap<String, Option<String>>,
+}
+
+impl Aliases {
+    /// Load the alias file and, when tracking is enabled, update removal tracking
+    /// against the `current` slugs.
+    pub fn load(current: &BTreeSet<String>) -> Self {
+        let path = std::env::var("ALIASES_FILE").unwrap_or_else(|_| "aliases.yaml".to_string());
+        let file = match std::fs::read_to_string(&path) {
+            Err(_) => AliasFile::default(),
+            Ok(yaml) => match serde_yaml::from_str::<AliasFile>(&yaml) {
+                Ok(file) => {
+                    tracing::info!("Loaded {} renamed and {} removed scheme aliases from {}",
+                        file.renamed.len(), file.removed.len(), path);
+                    file
+                }
+                Err(e) => {
+                    tracing::warn!("Ignoring {}: {}", path, e);
+                    AliasFile::default()
+                }
+            },
+        };
+        for (from, to) in &file.renamed {
+            if !current.contains(&to.to_lowercase()) {
+                tracing::warn!("Alias {} -> {} points at a scheme that doesn't exist", from, to);
+            }
+        }
+
+        let removed = match TRACKING_FILE.get() {
+            Some(tracking_file) => update_tracking(tracking_file, current),
+            None => BTreeSet::new(),
+        };
+        Self::merge(file, removed)
+    }
+
+    /// Entries from the alias file override tracked removals.
+    fn merge(file: AliasFile, tracked: BTreeSet<String>) -> Self {
+        let lower = |s: String| s.to_lowercase();
+        let renamed: HashMap<String, String> = file.renamed.into_iter().map(|(k, v)| (lower(k), lower(v))).collect();
+        let mut removed: HashMap<String, Option<String>> = tracked.into_iter().map(|slug| (slug, None)).collect();
+        removed.extend(file.removed.into_iter().map(|(k, v)| (lower(k), v.map(lower))));
+        removed.retain(|slug, _| !renamed.contains_key(slug));
+        Aliases { renamed, removed }
+    }
+
+    pub fn lookup(&self, slug: &str) -> Option<Alias<'_>> {
+        let slug = slug.to_lowercase();
+        if let Some(to) = self.renamed.get(&slug) {
+            return Some(Alias::Renamed(to));
+        }
+        self.removed.get(&slug).map(|successor| Alias::Removed(successor.as_deref()))
+    }
+}
+
+/// Read the previous slugs from `path`, record the ones missing from `current` and write
+/// the result back. Returns every tracked removal.
+fn update_tracking(path: &PathBuf, current: &BTreeSet<String>) -> BTreeSet<String> {
+    let previous = std::fs::read_to_string(path).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let tracked = track(previous, current);
+    let written = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+    .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(&tracked).unwrap()));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write {}: {}", path.display(), e);
+    }
+    tracked.removed
+}
+
+fn track(previous: TrackedSlugs, current: &BTreeSet<String>) -> TrackedSlugs {
+    // An empty corpus means the data directory is missing, not that everything was removed
+    if current.is_empty() {
+        return previous;
+    }
+    let mut removed = previous.removed;
+    removed.retain(|slug| !current.contains(slug));
+    removed.extend(previous.known.difference(current).cloned());
+    TrackedSlugs { known: current.clone(), removed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slugs(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_track_removed_slugs() {
+        let first = track(TrackedSlugs::default(), &slugs(&["gruvbox-dark-hard", "monokai"]));
+        assert!(first.removed.is_empty());
+
+        let second = track(first, &slugs(&["gruvbox-dark", "monokai"]));
+        assert_eq!(second.removed, slugs(&["gruvbox-dark-hard"]));
+
+        // A missing corpus changes nothing; a slug that comes back is no longer removed
+        let third = track(second, &BTreeSet::new());
+        assert_eq!(third.removed.len(), 1);
+        let fourth = track(third, &slugs(&["gruvbox-dark", "gruvbox-dark-hard", "monokai"]));
+        assert!(fourth.removed.is_empty());
+    }
+
+    #[test]
+    fn test_update_tracking_writes_the_configured_file() {
+        let dir = std::env::temp_dir().join(format!("base16-slug-tracking-{}", std::process::id()));
+        let path = dir.join("nested").join("slugs.json");
+        assert!(update_tracking(&path, &slugs(&["monokai", "dracula"])).is_empty());
+        assert_eq!(update_tracking(&path, &slugs(&["monokai"])), slugs(&["dracula"]));
+        let tracked: TrackedSlugs = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(tracked.known, slugs(&["monokai"]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_alias_file_overrides_tracking() {
+        let file: AliasFile = serde_yaml::from_str("renamed:\n  Old-Name: new-name\nremoved:\n  gone: ~\n  dropped: kept\n").unwrap();
+        let aliases = Aliases::merge(file, slugs(&["old-name", "dropped", "vanished"]));
+        assert_eq!(aliases.lookup("old-name"), Some(Alias::Renamed("new-name")));
+        assert_eq!(aliases.lookup("gone"), Some(Alias::Removed(None)));
+        assert_eq!(aliases.lookup("DROPPED"), Some(Alias::Removed(Some("kept"))));
+        assert_eq!(aliases.lookup("vanished"), Some(Alias::Removed(None)));
+        assert_eq!(aliases.lookup("monokai"), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! Packing rendered template files into downloadable `.tar.gz` and `.zip` archives.
+
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    TarGz,
+    Zip,
+}
+
+impl Format {
+    /// Split an archive extension off a URL segment: `vim.tar.gz` -> (`vim`, TarGz).
+    pub fn split(segment: &str) -> (&str, Option<Format>) {
+        if let Some(name) = segment.strip_suffix(".tar.gz") {
+            return (name, Some(Format::TarGz));
+        }
+        if let Some(name) = segment.strip_suffix(".zip") {
+            return (name, Some(Format::Zip));
+        }
+        (segment, None)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::TarGz => ".tar.gz",
+            Format::Zip => ".zip",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::TarGz => "application/gzip",
+            Format::Zip => "application/zip",
+        }
+    }
+}
+
+/// Writes `.tar.gz` entries one at a time, so an archive never has to fit in memory.
+pub struct TarGzWriter<W: Write> {
+    tar: tar::Builder<flate2::write::GzEncoder<W>>,
+}
+
+impl<W: Write> TarGzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+        TarGzWriter { tar: tar::Builder::new(encoder) }
+    }
+
+    pub fn append(&mut self, path: &str, contents: &[u8]) -> std::io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        self.tar.append_data(&mut header, path, contents)
+    }
+
+    pub fn finish(self) -> std::io::Result<W> {
+        self.tar.into_inner()?.finish()
+    }
+}
+
+/// Pack `(path, contents)` pairs. Paths are relative and use `/`; a path that appears
+/// twice is an error, since one of the files would be lost.
+pub fn build(format: Format, files: &[(String, String)]) -> std::io::Result<Vec<u8>> {
+    let mut seen = std::collections::HashSet::new();
+    if let Some((path, _)) = files.iter().find(|(path, _)| !seen.insert(path.as_str())) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{} appears twice", path)));
+    }
+    match format {
+        Format::TarGz => {
+            let mut tar = TarGzWriter::new(Vec::new());
+            for (path, contents) in files {
+                tar.append(path, contents.as_bytes())?;
+            }
+            tar.finish()
+        }
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .unix_permissions(0o644);
+            for (path, contents) in files {
+                zip.start_file(path.as_str(), options)?;
+                zip.write_all(contents.as_bytes())?;
+            }
+            Ok(zip.finish()?.into_inner())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn files() -> Vec<(String, String)> {
+        vec![
+            ("colors/base16-monokai.vim".to_string(), "hi Normal".to_string()),
+            ("plugin/base16.vim".to_string(), "let g:x = 1".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(Format::split("vim.tar.gz"), ("vim", Some(Format::TarGz)));
+        assert_eq!(Format::split("tinted-vim:vim.zip"), ("tinted-vim:vim", Some(Format::Zip)));
+        assert_eq!(Format::split("vim"), ("vim", None));
+    }
+
+    #[test]
+    fn test_tar_gz_round_trip() {
+        let bytes = build(Format::TarGz, &files()).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(bytes.as_slice()));
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            entries.push((entry.path().unwrap().to_string_lossy().to_string(), contents));
+        }
+        assert_eq!(entries, files());
+    }
+
+    #[test]
+    fn test_duplicate_paths_are_rejected() {
+        let mut files = files();
+        files.push(("colors/base16-monokai.vim".to_string(), "duplicate".to_string()));
+        for format in [Format::TarGz, Format::Zip] {
+            let error = build(format, &files).unwrap_err();
+            assert_eq!(error.to_string(), "colors/base16-monokai.vim appears twice");
+        }
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let bytes = build(Format::Zip, &files()).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut contents = String::new();
+        archive.by_name("plugin/base16.vim").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "let g:x = 1");
+    }
+}
@@ -0,0 +1,171 @@
+//! Colour conversions shared by the palette tooling.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Perceptual colour in OKLab (https://bottosson.github.io/posts/oklab/).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Rgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Parse `#rgb`, `#rrggbb` or `#rrggbbaa` (alpha is dropped), with or without `#`.
+    pub fn from_hex(value: &str) -> Option<Rgb> {
+        let hex = value.trim().trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let hex: String = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex[..6].to_string(),
+            _ => return None,
+        };
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Rgb::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Lowercase hex digits without the leading `#`.
+    pub fn hex_digits(self) -> String {
+        format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    pub fn to_oklab(self) -> Oklab {
+        let r = srgb_to_linear(self.r);
+        let g = srgb_to_linear(self.g);
+        let b = srgb_to_linear(self.b);
+
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        Oklab {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+}
+
+impl Oklab {
+    pub fn chroma(&self) -> f64 {
+        (self.a * self.a + self.b * self.b).sqrt()
+    }
+
+    /// Hue angle in degrees, 0..360.
+    pub fn hue(&self) -> f64 {
+        let h = self.b.atan2(self.a).to_degrees();
+        if h < 0.0 { h + 360.0 } else { h }
+    }
+
+    pub fn from_lch(l: f64, c: f64, h: f64) -> Self {
+        let h = h.to_radians();
+        Oklab { l, a: c * h.cos(), b: c * h.sin() }
+    }
+
+    pub fn distance(&self, other: &Oklab) -> f64 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)).sqrt()
+    }
+
+    pub fn lerp(&self, other: &Oklab, t: f64) -> Oklab {
+        Oklab {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+
+    /// Convert back to sRGB, reducing chroma until the colour fits in gamut.
+    pub fn to_rgb(self) -> Rgb {
+        let (c, h) = (self.chroma(), self.hue());
+        let mut scale = 1.0;
+        loop {
+            let candidate = Oklab::from_lch(self.l, c * scale, h);
+            if let Some(rgb) = candidate.to_rgb_exact() {
+                return rgb;
+            }
+            if scale <= 0.0 {
+                return candidate.to_rgb_clamped();
+            }
+            scale -= 0.05;
+        }
+    }
+
+    fn to_linear(self) -> [f64; 3] {
+        let l = (self.l + 0.3963377774 * self.a + 0.2158037573 * self.b).powi(3);
+        let m = (self.l - 0.1055613458 * self.a - 0.0638541728 * self.b).powi(3);
+        let s = (self.l - 0.0894841775 * self.a - 1.2914855480 * self.b).powi(3);
+        [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ]
+    }
+
+    fn to_rgb_exact(self) -> Option<Rgb> {
+        let linear = self.to_linear();
+        if linear.iter().any(|v| !(-0.0001..=1.0001).contains(v)) {
+            return None;
+        }
+        Some(self.to_rgb_clamped())
+    }
+
+    fn to_rgb_clamped(self) -> Rgb {
+        let [r, g, b] = self.to_linear();
+        Rgb::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+}
+
+fn srgb_to_linear(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f64) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let s = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (s * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_from_hex_variants() {
+        assert_eq!(Rgb::from_hex("#F92672"), Some(Rgb::new(0xf9, 0x26, 0x72)));
+        assert_eq!(Rgb::from_hex("f92672"), Some(Rgb::new(0xf9, 0x26, 0x72)));
+        assert_eq!(Rgb::from_hex("#abc"), Some(Rgb::new(0xaa, 0xbb, 0xcc)));
+        assert_eq!(Rgb::from_hex("#f92672ff"), Some(Rgb::new(0xf9, 0x26, 0x72)));
+        assert_eq!(Rgb::from_hex("#f9267"), None);
+        assert_eq!(Rgb::from_hex("#gggggg"), None);
+    }
+
+    #[test]
+    fn test_oklab_roundtrip() {
+        for rgb in [Rgb::new(0, 0, 0), Rgb::new(255, 255, 255), Rgb::new(249, 38, 114), Rgb::new(102, 217, 239)] {
+            assert_eq!(rgb.to_oklab().to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn test_oklab_white_lightness() {
+        let white = Rgb::new(255, 255, 255).to_oklab();
+        assert!((white.l - 1.0).abs() < 0.001);
+        assert!(white.chroma() < 0.001);
+    }
+}
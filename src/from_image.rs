@@ -0,0 +1,319 @@
+//! Derive a Base16 palette from an uploaded image.
+//!
+//! Pixels are clustered with k-means in OKLab, the background/foreground ramp
+//! (base00-base07) is built from the dominant dark and light clusters, and the
+//! eight accents (base08-base0F) are picked by hue to match the slot meanings
+//! from `get_base_description`.
+
+use crate::color::{Oklab, Rgb};
+use crate::palette::Slot;
+use tiny_skia::Pixmap;
+
+const MAX_SAMPLES: usize = 8192;
+/// Larger images are refused before decoding: a small upload can decompress to gigabytes.
+const MAX_PIXELS: u64 = 25_000_000;
+const CLUSTERS: usize = 12;
+const ITERATIONS: usize = 16;
+
+/// Hue targets (OKLab degrees) for base08-base0E. base0F is derived from orange.
+const ACCENT_HUES: [(Slot, f64); 7] = [
+    (Slot::Base08, 25.0),  // red
+    (Slot::Base09, 55.0),  // orange
+    (Slot::Base0A, 95.0),  // yellow
+    (Slot::Base0B, 140.0), // green
+    (Slot::Base0C, 195.0), // cyan
+    (Slot::Base0D, 255.0), // blue
+    (Slot::Base0E, 320.0), // magenta
+];
+
+pub struct ExtractedPalette {
+    pub variant: &'static str,
+    pub colors: Vec<(Slot, Rgb)>,
+}
+
+#[derive(Clone, Copy)]
+struct Cluster {
+    center: Oklab,
+    weight: usize,
+}
+
+/// Decode a PNG or JPEG into a subsample of opaque pixels. The dimensions are read
+/// from the header first, so images over `MAX_PIXELS` are refused before decoding.
+pub fn decode_image(bytes: &[u8]) -> Result<Vec<Rgb>, String> {
+    let pixels = if bytes.starts_with(b"\x89PNG") {
+        let (width, height) = png_dimensions(bytes).ok_or("Invalid PNG: missing IHDR header")?;
+        check_dimensions(width, height)?;
+        let pixmap = Pixmap::decode_png(bytes).map_err(|e| format!("Invalid PNG: {}", e))?;
+        let stride = pixmap.pixels().len().div_ceil(MAX_SAMPLES);
+        pixmap.pixels().iter()
+            .step_by(stride)
+            .filter(|p| p.alpha() > 0)
+            .map(|p| {
+                let c = p.demultiply();
+                Rgb::new(c.red(), c.green(), c.blue())
+            })
+            .collect::<Vec<_>>()
+    } else if bytes.starts_with(b"\xFF\xD8") {
+        let mut decoder = zune_jpeg::JpegDecoder::new(bytes);
+        decoder.decode_headers().map_err(|e| format!("Invalid JPEG: {:?}", e))?;
+        let info = decoder.info().ok_or("Invalid JPEG: missing frame header")?;
+        check_dimensions(info.width as u64, info.height as u64)?;
+        let data = decoder.decode().map_err(|e| format!("Invalid JPEG: {:?}", e))?;
+        let channels = decoder.get_output_colorspace().map(|c| c.num_components()).unwrap_or(3);
+        let stride = (data.len() / channels.max(1)).div_ceil(MAX_SAMPLES).max(1);
+        match channels {
+            1 => data.iter().step_by(stride).map(|&v| Rgb::new(v, v, v)).collect(),
+            n if n >= 3 => data.chunks_exact(n).step_by(stride).map(|c| Rgb::new(c[0], c[1], c[2])).collect(),
+            _ => return Err("Unsupported JPEG colour space".to_string()),
+        }
+    } else {
+        return Err("Expected a PNG or JPEG image".to_string());
+    };
+
+    if pixels.is_empty() {
+        return Err("Image has no opaque pixels".to_string());
+    }
+    Ok(pixels)
+}
+
+/// Width and height from the IHDR chunk, which the PNG spec requires to come first.
+fn png_dimensions(bytes: &[u8]) -> Option<(u64, u64)> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let read = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64);
+    Some((read(16)?, read(20)?))
+}
+
+fn check_dimensions(width: u64, height: u64) -> Result<(), String> {
+    if width * height > MAX_PIXELS {
+        return Err(format!("Image is {}x{}; at most {} megapixels are accepted", width, height, MAX_PIXELS / 1_000_000));
+    }
+    Ok(())
+}
+
+/// Build a Base16 palette from sampled pixels. `variant` forces "dark" or "light".
+pub fn extract_palette(pixels: &[Rgb], variant: Option<&str>) -> ExtractedPalette {
+    let samples: Vec<Oklab> = pixels.iter().map(|p| p.to_oklab()).collect();
+    let clusters = kmeans(&samples, CLUSTERS);
+
+    let total: usize = clusters.iter().map(|c| c.weight).sum::<usize>().max(1);
+    let mean_l = clusters.iter().map(|c| c.center.l * c.weight as f64).sum::<f64>() / total as f64;
+    let is_dark = match variant {
+        Some("light") => false,
+        Some("dark") => true,
+        _ => mean_l < 0.6,
+    };
+
+    let mut colors = build_ramp(&clusters, is_dark);
+    colors.extend(build_accents(&clusters, is_dark));
+
+    ExtractedPalette {
+        variant: if is_dark { "dark" } else { "light" },
+        colors,
+    }
+}
+
+fn kmeans(samples: &[Oklab], k: usize) -> Vec<Cluster> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    // Seed deterministically from lightness quantiles so uploads give stable output
+    let mut by_lightness: Vec<&Oklab> = samples.iter().collect();
+    by_lightness.sort_by(|a, b| a.l.partial_cmp(&b.l).unwrap());
+    let k = k.min(samples.len());
+    let mut centers: Vec<Oklab> = (0..k)
+        .map(|i| *by_lightness[(i * 2 + 1) * by_lightness.len() / (k * 2)])
+        .collect();
+
+    let mut assignment = vec![0usize; samples.len()];
+    for _ in 0..ITERATIONS {
+        for (i, sample) in samples.iter().enumerate() {
+            assignment[i] = nearest(&centers, sample);
+        }
+
+        let mut sums = vec![(0.0, 0.0, 0.0, 0usize); k];
+        for (sample, &c) in samples.iter().zip(&assignment) {
+            sums[c].0 += sample.l;
+            sums[c].1 += sample.a;
+            sums[c].2 += sample.b;
+            sums[c].3 += 1;
+        }
+        for (center, (l, a, b, n)) in centers.iter_mut().zip(&sums) {
+            if *n > 0 {
+                let n = *n as f64;
+                *center = Oklab { l: l / n, a: a / n, b: b / n };
+            }
+        }
+    }
+
+    let mut weights = vec![0usize; k];
+    for &c in &assignment {
+        weights[c] += 1;
+    }
+    centers.into_iter()
+        .zip(weights)
+        .filter(|(_, w)| *w > 0)
+        .map(|(center, weight)| Cluster { center, weight })
+        .collect()
+}
+
+fn nearest(centers: &[Oklab], sample: &Oklab) -> usize {
+    centers.iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.distance(sample).partial_cmp(&b.distance(sample)).unwrap())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// base00-base07: background to foreground, tinted with the image's dominant neutrals.
+fn build_ramp(clusters: &[Cluster], is_dark: bool) -> Vec<(Slot, Rgb)> {
+    let dark_side = clusters.iter().filter(|c| c.center.l < 0.5).max_by_key(|c| c.weight);
+    let light_side = clusters.iter().filter(|c| c.center.l >= 0.5).max_by_key(|c| c.weight);
+    let (bg, fg) = match is_dark {
+        true => (dark_side, light_side),
+        false => (light_side, dark_side),
+    };
+
+    // Keep the neutrals only lightly tinted so text stays readable
+    let tint = |c: Option<&Cluster>| -> (f64, f64) {
+        c.map(|c| (c.center.chroma().min(0.035), c.center.hue())).unwrap_or((0.0, 0.0))
+    };
+    let (bg_c, bg_h) = tint(bg);
+    let (fg_c, fg_h) = tint(fg);
+
+    let lightness: [f64; 8] = match is_dark {
+        true => {
+            let base = bg.map(|c| c.center.l).unwrap_or(0.22).clamp(0.16, 0.3);
+            [base, base + 0.05, base + 0.11, base + 0.26, 0.72, 0.86, 0.92, 0.97]
+        }
+        false => {
+            let base = bg.map(|c| c.center.l).unwrap_or(0.97).clamp(0.9, 0.99);
+            [base, base - 0.04, base - 0.1, base - 0.3, 0.48, 0.38, 0.28, 0.2]
+        }
+    };
+
+    Slot::ALL[..8].iter().zip(lightness).enumerate()
+        .map(|(i, (slot, l))| {
+            let t = i as f64 / 7.0;
+            let from = Oklab::from_lch(l, bg_c, bg_h);
+            let to = Oklab::from_lch(l, fg_c, fg_h);
+            (*slot, from.lerp(&to, t).to_rgb())
+        })
+        .collect()
+}
+
+/// base08-base0F: the most colourful cluster near each slot's hue, or a synthesised
+/// colour at that hue when the image has nothing close.
+fn build_accents(clusters: &[Cluster], is_dark: bool) -> Vec<(Slot, Rgb)> {
+    let chromatic: Vec<&Cluster> = clusters.iter().filter(|c| c.center.chroma() >= 0.04).collect();
+
+    let (l_range, default_l) = match is_dark {
+        true => (0.62..=0.85, 0.72),
+        false => (0.42..=0.62, 0.52),
+    };
+    let total: usize = chromatic.iter().map(|c| c.weight).sum();
+    let (mean_l, mean_c) = match total {
+        0 => (default_l, 0.12),
+        _ => (
+            chromatic.iter().map(|c| c.center.l * c.weight as f64).sum::<f64>() / total as f64,
+            chromatic.iter().map(|c| c.center.chroma() * c.weight as f64).sum::<f64>() / total as f64,
+        ),
+    };
+    let mean_l = mean_l.clamp(*l_range.start(), *l_range.end());
+    let mean_c = mean_c.clamp(0.08, 0.2);
+
+    let mut used: Vec<usize> = Vec::new();
+    let mut accents: Vec<(Slot, Rgb)> = ACCENT_HUES.iter()
+        .map(|&(slot, hue)| {
+            let best = chromatic.iter().enumerate()
+                .filter(|(i, _)| !used.contains(i))
+                .map(|(i, c)| (i, c, hue_distance(c.center.hue(), hue)))
+                .filter(|(_, _, d)| *d <= 30.0)
+                .max_by(|(_, a, da), (_, b, db)| {
+                    let score_a = a.center.chroma() * (a.weight as f64).ln_1p() / (1.0 + da / 10.0);
+                    let score_b = b.center.chroma() * (b.weight as f64).ln_1p() / (1.0 + db / 10.0);
+                    score_a.partial_cmp(&score_b).unwrap()
+                });
+
+            let color = match best {
+                Some((i, c, _)) => {
+                    used.push(i);
+                    let l = c.center.l.clamp(*l_range.start(), *l_range.end());
+                    Oklab::from_lch(l, c.center.chroma().max(0.08), c.center.hue())
+                }
+                None => Oklab::from_lch(mean_l, mean_c, hue),
+            };
+            (slot, color.to_rgb())
+        })
+        .collect();
+
+    // base0F (deprecated/embedded) is conventionally a muted brown
+    let brown_l = if is_dark { mean_l - 0.15 } else { mean_l - 0.05 };
+    accents.push((Slot::Base0F, Oklab::from_lch(brown_l, mean_c * 0.6, 50.0).to_rgb()));
+    accents
+}
+
+fn hue_distance(a: f64, b: f64) -> f64 {
+    let d = (a - b).abs() % 360.0;
+    if d > 180.0 { 360.0 - d } else { d }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn striped_png() -> Vec<u8> {
+        let stripes = [
+            (30, 30, 40), (30, 30, 40), (30, 30, 40), (230, 230, 220),
+            (220, 50, 60), (60, 180, 80), (50, 100, 220),
+        ];
+        let mut pixmap = Pixmap::new(70, 10).unwrap();
+        for (i, px) in pixmap.pixels_mut().iter_mut().enumerate() {
+            let (r, g, b) = stripes[(i % 70) / 10];
+            *px = tiny_skia::ColorU8::from_rgba(r, g, b, 255).premultiply();
+        }
+        pixmap.encode_png().unwrap()
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_format() {
+        assert!(decode_image(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_png_before_decoding() {
+        // Only the signature and IHDR: a 100000x100000 image would be 40GB decoded
+        let mut png = striped_png();
+        png[16..20].copy_from_slice(&100_000u32.to_be_bytes());
+        png[20..24].copy_from_slice(&100_000u32.to_be_bytes());
+        png.truncate(33);
+        let err = decode_image(&png).unwrap_err();
+        assert!(err.contains("megapixels"), "{}", err);
+    }
+
+    #[test]
+    fn test_extract_palette_from_png() {
+        let pixels = decode_image(&striped_png()).unwrap();
+        let palette = extract_palette(&pixels, None);
+
+        assert_eq!(palette.variant, "dark");
+        assert_eq!(palette.colors.len(), 16);
+        let get = |slot: Slot| palette.colors.iter().find(|(s, _)| *s == slot).unwrap().1;
+
+        // Background well separated from the foreground
+        assert!(get(Slot::Base05).to_oklab().l - get(Slot::Base00).to_oklab().l > 0.5);
+        // Red stripe should land on base08, blue on base0D
+        assert!(get(Slot::Base08).r > get(Slot::Base08).b);
+        assert!(get(Slot::Base0D).b > get(Slot::Base0D).r);
+    }
+
+    #[test]
+    fn test_extract_palette_forced_light() {
+        let pixels = decode_image(&striped_png()).unwrap();
+        let palette = extract_palette(&pixels, Some("light"));
+        assert_eq!(palette.variant, "light");
+        assert!(palette.colors[0].1.to_oklab().l > palette.colors[5].1.to_oklab().l);
+    }
+}
@@ -0,0 +1,189 @@
+//! Lightweight highlighting for rendered template previews: comments, strings, numbers
+//! and colour literals, with the comment and quote syntax picked by file extension.
+//! Output is escaped HTML using the `comment`, `string`, `number` and `hex-color`
+//! classes of the scheme pages.
+
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Line comments only count as the first thing on a line (vim's `"`).
+    comments_at_line_start: bool,
+}
+
+impl Syntax {
+    fn for_extension(extension: &str) -> Syntax {
+        let syntax = |line_comments, block_comment, quotes| Syntax {
+            line_comments,
+            block_comment,
+            quotes,
+            comments_at_line_start: false,
+        };
+        match extension.trim_start_matches('.').to_ascii_lowercase().as_str() {
+            "vim" => Syntax { comments_at_line_start: true, ..syntax(&["\""], None, &['\'', '"']) },
+            "lua" => syntax(&["--"], Some(("--[[", "]]")), &['"', '\'']),
+            "css" | "scss" | "less" => syntax(&[], Some(("/*", "*/")), &['"', '\'']),
+            "js" | "ts" | "json" | "jsonc" | "rasi" | "kdl" | "c" | "h" | "rs" | "swift" | "kt" | "java" | "go" =>
+                syntax(&["//"], Some(("/*", "*/")), &['"', '\'']),
+            "xml" | "plist" | "tmtheme" | "xaml" | "svg" | "html" => syntax(&[], Some(("<!--", "-->")), &['"']),
+            "ini" | "cfg" | "reg" => syntax(&[";", "#"], None, &['"']),
+            "el" | "lisp" | "scm" | "clj" => syntax(&[";"], None, &['"']),
+            "hs" | "sql" => syntax(&["--"], None, &['"', '\'']),
+            _ => syntax(&["#"], None, &['"', '\'']),
+        }
+    }
+}
+
+pub fn highlight(source: &str, extension: &str) -> String {
+    let syntax = Syntax::for_extension(extension);
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut rest = source;
+    let mut line_start = true;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        let token_len = if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let len = rest[open.len()..].find(close).map_or(rest.len(), |i| open.len() + i + close.len());
+            push_span(&mut out, "comment", &rest[..len]);
+            len
+        } else if let Some(len) = hex_color_len(rest) {
+            push_color(&mut out, &rest[..len]);
+            len
+        } else if syntax.line_comments.iter().any(|p| rest.starts_with(p))
+            && (line_start || !syntax.comments_at_line_start) {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            push_span(&mut out, "comment", &rest[..len]);
+            len
+        } else if syntax.quotes.contains(&c) {
+            let len = string_len(rest, c);
+            out.push_str("<span class=\"string\">");
+            push_with_colors(&mut out, &rest[..len]);
+            out.push_str("</span>");
+            len
+        } else if c.is_ascii_digit() && !prev.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '-') {
+            let len = rest.find(|ch: char| !(ch.is_ascii_digit() || ch == '.')).unwrap_or(rest.len());
+            match rest[len..].chars().next() {
+                Some(next) if next.is_alphabetic() || next == '_' => {
+                    push_escaped(&mut out, &rest[..len]);
+                }
+                _ => push_span(&mut out, "number", &rest[..len]),
+            }
+            len
+        } else {
+            push_escaped(&mut out, &rest[..c.len_utf8()]);
+            c.len_utf8()
+        };
+
+        let token = &rest[..token_len];
+        line_start = match token.rfind('\n') {
+            Some(i) => token[i + 1..].chars().all(char::is_whitespace),
+            None => line_start && token.chars().all(char::is_whitespace),
+        };
+        prev = token.chars().last();
+        rest = &rest[token_len..];
+    }
+    out
+}
+
+/// `#rrggbb` or `#rrggbbaa` not followed by more word characters.
+fn hex_color_len(text: &str) -> Option<usize> {
+    let digits = text.strip_prefix('#')?;
+    let count = digits.chars().take_while(char::is_ascii_hexdigit).count();
+    let boundary = !digits[count..].chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_');
+    (matches!(count, 6 | 8) && boundary).then_some(count + 1)
+}
+
+/// A quoted string up to its closing quote or the end of the line, skipping escapes.
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '\n' => return i,
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn push_span(out: &mut String, class: &str, text: &str) {
+    out.push_str("<span class=\"");
+    out.push_str(class);
+    out.push_str("\">");
+    push_escaped(out, text);
+    out.push_str("</span>");
+}
+
+fn push_color(out: &mut String, hex: &str) {
+    out.push_str("<span class=\"hex-color\" style=\"color: ");
+    out.push_str(&hex[..7]);
+    out.push_str(";\">");
+    out.push_str(hex);
+    out.push_str("</span>");
+}
+
+fn push_with_colors(out: &mut String, text: &str) {
+    let mut rest = text;
+    while let Some(i) = rest.find('#') {
+        push_escaped(out, &rest[..i]);
+        rest = &rest[i..];
+        match hex_color_len(rest) {
+            Some(len) => {
+                push_color(out, &rest[..len]);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('#');
+                rest = &rest[1..];
+            }
+        }
+    }
+    push_escaped(out, rest);
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_shell() {
+        let html = highlight("# base16 <shell>\ncolor00=\"27/28/22\"\nprintf '#f8f8f2' 16\n", ".sh");
+        assert!(html.starts_with("<span class=\"comment\"># base16 &lt;shell&gt;</span>\n"));
+        assert!(html.contains("<span class=\"string\">&quot;27/28/22&quot;</span>"));
+        assert!(html.contains("<span class=\"string\">&#39;<span class=\"hex-color\" style=\"color: #f8f8f2;\">#f8f8f2</span>&#39;</span>"));
+        assert!(html.contains("<span class=\"number\">16</span>"));
+        // Digits inside names stay plain
+        assert!(!highlight("color00=x", "sh").contains("number"));
+    }
+
+    #[test]
+    fn test_highlight_by_extension() {
+        // vim: `"` comments only start a line; elsewhere it's a string
+        let html = highlight("\" comment\nlet g:x = \"y\"", "vim");
+        assert!(html.starts_with("<span class=\"comment\">&quot; comment</span>"));
+        assert!(html.contains("<span class=\"string\">&quot;y&quot;</span>"));
+        // A colour in a conf file is not a comment
+        let html = highlight("foreground #f8f8f2 # note", "conf");
+        assert!(html.contains("class=\"hex-color\""));
+        assert!(html.ends_with("<span class=\"comment\"># note</span>"));
+        let html = highlight("/* a\nb */ a { color: #272822; }", "css");
+        assert!(html.starts_with("<span class=\"comment\">/* a\nb */</span>"));
+        assert!(html.contains("style=\"color: #272822;\""));
+        assert!(highlight("<!-- x --><dict>", ".plist").starts_with("<span class=\"comment\">&lt;!-- x --&gt;</span>&lt;dict&gt;"));
+    }
+}
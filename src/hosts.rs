@@ -0,0 +1,128 @@
+//! Host profiles: which schemes a hostname serves, which system bare slugs prefer,
+//! and the origin used for canonical URLs.
+//!
+//! Profiles are read from the YAML file named by `HOSTS_FILE` (default `hosts.yaml`)
+//! when it exists, otherwise base16.sh and base24.sh are built in. The first profile
+//! is used for hosts that match none of them (localhost, mirrors without an entry).
+
+use axum::http::{HeaderMap, header};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HostProfile {
+    /// Hostname without port; `www.` is stripped before matching.
+    pub host: String,
+    /// Scheme and base URL for canonical links, e.g. `https://base16.sh`.
+    pub origin: String,
+    /// Systems whose schemes this host serves.
+    pub systems: Vec<String>,
+    /// System a bare slug resolves to when it exists in several served systems.
+    pub default_system: String,
+}
+
+impl HostProfile {
+    pub fn serves(&self, system: &str) -> bool {
+        self.systems.iter().any(|s| s == system)
+    }
+
+    /// The only system served, for single-system hosts.
+    pub fn single_system(&self) -> Option<&str> {
+        match self.systems.as_slice() {
+            [system] => Some(system),
+            _ => None,
+        }
+    }
+}
+
+pub struct HostProfiles {
+    profiles: Vec<HostProfile>,
+}
+
+impl HostProfiles {
+    pub fn load() -> Self {
+        let path = std::env::var("HOSTS_FILE").unwrap_or_else(|_| "hosts.yaml".to_string());
+        let Ok(yaml) = std::fs::read_to_string(&path) else {
+            return Self::builtin();
+        };
+        match Self::parse(&yaml) {
+            Ok(profiles) => {
+                tracing::info!("Loaded {} host profiles from {}", profiles.profiles.len(), path);
+                profiles
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring {}: {}", path, e);
+                Self::builtin()
+            }
+        }
+    }
+
+    fn parse(yaml: &str) -> Result<Self, String> {
+        let profiles: Vec<HostProfile> = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+        if profiles.is_empty() {
+            return Err("no profiles defined".to_string());
+        }
+        for profile in &profiles {
+            if let Some(system) = profile.systems.iter().find(|s| !matches!(s.as_str(), "base16" | "base24")) {
+                return Err(format!("{}: unknown system '{}'", profile.host, system));
+            }
+            if !profile.serves(&profile.default_system) {
+                return Err(format!("{}: default-system '{}' is not in systems", profile.host, profile.default_system));
+            }
+        }
+        Ok(HostProfiles { profiles })
+    }
+
+    fn builtin() -> Self {
+        let profile = |host: &str, systems: &[&str], default_system: &str| HostProfile {
+            host: host.to_string(),
+            origin: format!("https://{}", host),
+            systems: systems.iter().map(|s| s.to_string()).collect(),
+            default_system: default_system.to_string(),
+        };
+        HostProfiles {
+            profiles: vec![
+                profile("base16.sh", &["base16", "base24"], "base16"),
+                profile("base24.sh", &["base24"], "base24"),
+            ],
+        }
+    }
+
+    pub fn for_host(&self, host: &str) -> &HostProfile {
+        let host = host.split(':').next().unwrap_or("").to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        self.profiles.iter()
+            .find(|p| p.host.eq_ignore_ascii_case(host))
+            .unwrap_or(&self.profiles[0])
+    }
+
+    pub fn for_headers(&self, headers: &HeaderMap) -> &HostProfile {
+        self.for_host(headers.get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or(""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = HostProfiles::builtin();
+        assert_eq!(profiles.for_host("base24.sh").default_system, "base24");
+        assert_eq!(profiles.for_host("www.base24.sh:443").host, "base24.sh");
+        assert_eq!(profiles.for_host("localhost:3000").host, "base16.sh");
+        assert!(profiles.for_host("base16.sh").serves("base24"));
+        assert!(!profiles.for_host("base24.sh").serves("base16"));
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let yaml = "- host: themes.internal\n  origin: http://themes.internal\n  systems: [base24]\n  default-system: base24\n";
+        let profiles = HostProfiles::parse(yaml).unwrap();
+        assert_eq!(profiles.for_host("anything").origin, "http://themes.internal");
+
+        assert!(HostProfiles::parse("[]").is_err());
+        let yaml = "- host: x\n  origin: http://x\n  systems: [base16]\n  default-system: base24\n";
+        assert!(HostProfiles::parse(yaml).is_err());
+    }
+}
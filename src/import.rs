@@ -0,0 +1,516 @@
+//! Import colour schemes from other terminal/editor theme formats.
+//!
+//! Every format is first read into `TerminalColors` (the 16 ANSI colours, tinted-shell's
+//! color16-color21 and background/foreground/selection), which is then mapped onto Base16 slots by
+//! inverting the tinted-shell mapping. Slots that have no ANSI counterpart are
+//! interpolated and reported as inferred.
+
+use crate::color::{Oklab, Rgb};
+use crate::palette::Slot;
+use std::collections::hash_map::{Entry, HashMap};
+
+pub const FORMATS: [&str; 6] = ["iterm", "windows-terminal", "xresources", "alacritty", "kitty", "vscode"];
+
+/// ANSI index -> Base16 slot, the inverse of tinted-shell's color00-color21.
+const ANSI_SLOTS: [(usize, Slot); 15] = [
+    (1, Slot::Base08), (2, Slot::Base0B), (3, Slot::Base0A), (4, Slot::Base0D),
+    (5, Slot::Base0E), (6, Slot::Base0C), (8, Slot::Base03), (15, Slot::Base07), (0, Slot::Base00),
+    (16, Slot::Base09), (17, Slot::Base0F), (18, Slot::Base01), (19, Slot::Base02), (20, Slot::Base04), (21, Slot::Base06),
+];
+
+/// Bright ANSI index -> Base24 slot, used when importing as base24.
+const ANSI_BRIGHT_SLOTS: [(usize, Slot); 6] = [
+    (9, Slot::Base12), (11, Slot::Base13), (10, Slot::Base14), (14, Slot::Base15), (12, Slot::Base16), (13, Slot::Base17),
+];
+
+const ANSI_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+#[derive(Debug, Default)]
+pub struct TerminalColors {
+    pub name: Option<String>,
+    /// color0-color15, then the color16-color21 that tinted-shell sets.
+    pub ansi: [Option<Rgb>; 22],
+    pub background: Option<Rgb>,
+    pub foreground: Option<Rgb>,
+    pub selection: Option<Rgb>,
+}
+
+#[derive(Debug)]
+pub struct ImportedScheme {
+    pub name: Option<String>,
+    /// "dark" or "light", from the background's lightness.
+    pub variant: &'static str,
+    pub palette: Vec<(Slot, Rgb)>,
+    pub direct: Vec<Slot>,
+    pub inferred: Vec<Slot>,
+}
+
+/// Parse `input` as `format` and map it onto `system` ("base16" or "base24") slots.
+pub fn import(format: &str, input: &str, system: &str) -> Result<ImportedScheme, String> {
+    let colors = match format {
+        "iterm" | "itermcolors" => parse_iterm(input)?,
+        "windows-terminal" | "wt" => parse_windows_terminal(input)?,
+        "xresources" | "xdefaults" => parse_xresources(input),
+        "alacritty" => parse_alacritty(input)?,
+        "kitty" => parse_kitty(input),
+        "vscode" => parse_vscode(input)?,
+        _ => return Err(format!("Unknown format '{}', expected one of: {}", format, FORMATS.join(", "))),
+    };
+    to_scheme(colors, system)
+}
+
+fn to_scheme(colors: TerminalColors, system: &str) -> Result<ImportedScheme, String> {
+    let mut direct: Vec<Slot> = Vec::new();
+    let mut slots: HashMap<Slot, Rgb> = HashMap::new();
+
+    for (index, slot) in ANSI_SLOTS {
+        if let Some(rgb) = colors.ansi[index] {
+            slots.insert(slot, rgb);
+            direct.push(slot);
+        }
+    }
+    // Explicit background/foreground win over their ANSI stand-ins; the selection only
+    // stands in for base02 when color19 doesn't give it
+    let selection = colors.selection.filter(|_| !slots.contains_key(&Slot::Base02));
+    let explicit = [(Slot::Base00, colors.background), (Slot::Base05, colors.foreground), (Slot::Base02, selection)];
+    for (slot, rgb) in explicit {
+        if let Some(rgb) = rgb {
+            slots.insert(slot, rgb);
+            if !direct.contains(&slot) {
+                direct.push(slot);
+            }
+        }
+    }
+    if !slots.contains_key(&Slot::Base05)
+        && let Some(white) = colors.ansi[7] {
+            slots.insert(Slot::Base05, white);
+            direct.push(Slot::Base05);
+        }
+
+    let (Some(&bg), Some(&fg)) = (slots.get(&Slot::Base00), slots.get(&Slot::Base05)) else {
+        return Err("Theme defines neither a background/foreground nor ANSI black/white".to_string());
+    };
+
+    let mut inferred: Vec<Slot> = Vec::new();
+    let mut infer = |slots: &mut HashMap<Slot, Rgb>, slot: Slot, value: Rgb| {
+        if let Entry::Vacant(entry) = slots.entry(slot) {
+            entry.insert(value);
+            inferred.push(slot);
+        }
+    };
+
+    let mix = |a: Rgb, b: Rgb, t: f64| a.to_oklab().lerp(&b.to_oklab(), t).to_rgb();
+    infer(&mut slots, Slot::Base03, mix(bg, fg, 0.4));
+    infer(&mut slots, Slot::Base07, mix(fg, if is_dark(bg) { Rgb::new(255, 255, 255) } else { Rgb::new(0, 0, 0) }, 0.5));
+    let base03 = slots[&Slot::Base03];
+    let base07 = slots[&Slot::Base07];
+    infer(&mut slots, Slot::Base02, mix(bg, base03, 0.55));
+    let base02 = slots[&Slot::Base02];
+    infer(&mut slots, Slot::Base01, mix(bg, base02, 0.5));
+    infer(&mut slots, Slot::Base04, mix(base03, fg, 0.5));
+    infer(&mut slots, Slot::Base06, mix(fg, base07, 0.5));
+
+    // Accents fall back to the ANSI bright variants, then to a neutral midpoint
+    let accent_fallbacks = [(Slot::Base08, 9), (Slot::Base0B, 10), (Slot::Base0A, 11), (Slot::Base0D, 12), (Slot::Base0E, 13), (Slot::Base0C, 14)];
+    for (slot, bright) in accent_fallbacks {
+        let value = colors.ansi[bright].unwrap_or_else(|| mix(bg, fg, 0.6));
+        infer(&mut slots, slot, value);
+    }
+
+    // base09 (orange) sits between red and yellow; base0F is a muted, darker orange
+    let orange = mix(slots[&Slot::Base08], slots[&Slot::Base0A], 0.5);
+    infer(&mut slots, Slot::Base09, orange);
+    let o = slots[&Slot::Base09].to_oklab();
+    infer(&mut slots, Slot::Base0F, Oklab::from_lch(o.l * 0.8, o.chroma() * 0.7, o.hue()).to_rgb());
+
+    if system == "base24" {
+        infer(&mut slots, Slot::Base10, mix(bg, Rgb::new(0, 0, 0), 0.25));
+        infer(&mut slots, Slot::Base11, mix(bg, Rgb::new(0, 0, 0), 0.5));
+        for (index, slot) in ANSI_BRIGHT_SLOTS {
+            match colors.ansi[index] {
+                Some(rgb) => {
+                    slots.insert(slot, rgb);
+                    direct.push(slot);
+                }
+                None => {
+                    let normal = slots[&ANSI_SLOTS.iter().find(|(i, _)| *i == index - 8).unwrap().1];
+                    infer(&mut slots, slot, mix(normal, Rgb::new(255, 255, 255), 0.2));
+                }
+            }
+        }
+    }
+
+    let mut palette: Vec<(Slot, Rgb)> = slots.into_iter().collect();
+    palette.sort_by_key(|(slot, _)| *slot);
+    direct.sort();
+    inferred.sort();
+
+    let variant = if is_dark(bg) { "dark" } else { "light" };
+    Ok(ImportedScheme { name: colors.name, variant, palette, direct, inferred })
+}
+
+fn is_dark(rgb: Rgb) -> bool {
+    rgb.to_oklab().l < 0.6
+}
+
+/// Parse `#rgb`, `#rrggbb`, `0xrrggbb` or X11 `rgb:r/g/b` colours.
+fn parse_color(value: &str) -> Option<Rgb> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    if let Some(rest) = value.strip_prefix("rgb:") {
+        let parts: Vec<u8> = rest.split('/')
+            .map(|p| {
+                let v = u16::from_str_radix(p, 16).ok()?;
+                match p.len() {
+                    1 => Some((v * 17) as u8),
+                    2 => Some(v as u8),
+                    n @ 3..=4 => Some((v >> (4 * (n - 2))) as u8),
+                    _ => None,
+                }
+            })
+            .collect::<Option<_>>()?;
+        return match parts[..] {
+            [r, g, b] => Some(Rgb::new(r, g, b)),
+            _ => None,
+        };
+    }
+    // VS Code allows #rrggbbaa; from_hex drops the alpha channel
+    Rgb::from_hex(value.strip_prefix("0x").unwrap_or(value))
+}
+
+fn parse_iterm(input: &str) -> Result<TerminalColors, String> {
+    let value: plist::Value = plist::from_bytes(input.as_bytes()).map_err(|e| format!("Invalid plist: {}", e))?;
+    let dict = value.as_dictionary().ok_or("Expected a plist dictionary")?;
+
+    let component = |color: &plist::Dictionary, key: &str| -> Option<u8> {
+        let v = color.get(key)?;
+        let v = v.as_real().or_else(|| v.as_signed_integer().map(|i| i as f64))?;
+        Some((v.clamp(0.0, 1.0) * 255.0).round() as u8)
+    };
+    let color = |key: &str| -> Option<Rgb> {
+        let c = dict.get(key)?.as_dictionary()?;
+        Some(Rgb::new(component(c, "Red Component")?, component(c, "Green Component")?, component(c, "Blue Component")?))
+    };
+
+    let mut colors = TerminalColors::default();
+    for (i, slot) in colors.ansi.iter_mut().enumerate().take(16) {
+        *slot = color(&format!("Ansi {} Color", i));
+    }
+    colors.background = color("Background Color");
+    colors.foreground = color("Foreground Color");
+    colors.selection = color("Selection Color");
+    Ok(colors)
+}
+
+fn parse_windows_terminal(input: &str) -> Result<TerminalColors, String> {
+    let json: serde_json::Value = serde_json::from_str(&strip_json_comments(input))
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+    // Accept a bare scheme object or a full settings.json with a "schemes" array
+    let scheme = json.get("schemes").and_then(|s| s.get(0)).unwrap_or(&json);
+    let get = |key: &str| scheme.get(key).and_then(|v| v.as_str()).and_then(parse_color);
+
+    let mut colors = TerminalColors {
+        name: scheme.get("name").and_then(|v| v.as_str()).map(String::from),
+        background: get("background"),
+        foreground: get("foreground"),
+        selection: get("selectionBackground"),
+        ..Default::default()
+    };
+    // Windows Terminal calls magenta "purple"
+    let names = ["black", "red", "green", "yellow", "blue", "purple", "cyan", "white"];
+    for (i, name) in names.iter().enumerate() {
+        colors.ansi[i] = get(name);
+        colors.ansi[i + 8] = get(&format!("bright{}{}", name[..1].to_uppercase(), &name[1..]));
+    }
+    Ok(colors)
+}
+
+fn parse_xresources(input: &str) -> TerminalColors {
+    let mut defines: HashMap<String, String> = HashMap::new();
+    let mut colors = TerminalColors::default();
+
+    for line in input.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("#define") {
+            let mut parts = rest.split_whitespace();
+            if let (Some(k), Some(v)) = (parts.next(), parts.next()) {
+                defines.insert(k.to_string(), v.to_string());
+            }
+            continue;
+        }
+        if line.starts_with('!') || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        // "*.color0", "URxvt*color0", "*background" all reduce to the last component
+        let key = key.trim().rsplit(['*', '.']).next().unwrap_or("").to_lowercase();
+        let value = value.trim();
+        let value = defines.get(value).map(String::as_str).unwrap_or(value);
+        let Some(rgb) = parse_color(value) else { continue };
+
+        match key.as_str() {
+            "background" => colors.background = Some(rgb),
+            "foreground" => colors.foreground = Some(rgb),
+            k => if let Some(i) = k.strip_prefix("color").and_then(|n| n.parse::<usize>().ok()).filter(|i| *i < 22) {
+                colors.ansi[i] = Some(rgb);
+            },
+        }
+    }
+    colors
+}
+
+fn parse_alacritty(input: &str) -> Result<TerminalColors, String> {
+    let value: toml::Value = toml::from_str(input).map_err(|e| format!("Invalid TOML: {}", e))?;
+    let section = |name: &str| value.get("colors").and_then(|c| c.get(name));
+    let get = |section_name: &str, key: &str| -> Option<Rgb> {
+        section(section_name)?.get(key)?.as_str().and_then(parse_color)
+    };
+
+    let mut colors = TerminalColors {
+        background: get("primary", "background"),
+        foreground: get("primary", "foreground"),
+        selection: get("selection", "background"),
+        ..Default::default()
+    };
+    for (i, name) in ANSI_NAMES.iter().enumerate() {
+        colors.ansi[i] = get("normal", name);
+        colors.ansi[i + 8] = get("bright", name);
+    }
+    Ok(colors)
+}
+
+fn parse_kitty(input: &str) -> TerminalColors {
+    let mut colors = TerminalColors::default();
+    for line in input.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            // kitty themes carry their name as "## name: Foo"
+            if let Some(name) = line.trim_start_matches('#').trim().strip_prefix("name:") {
+                colors.name = Some(name.trim().to_string());
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else { continue };
+        let Some(rgb) = parse_color(value) else { continue };
+        match key {
+            "background" => colors.background = Some(rgb),
+            "foreground" => colors.foreground = Some(rgb),
+            "selection_background" => colors.selection = Some(rgb),
+            k => if let Some(i) = k.strip_prefix("color").and_then(|n| n.parse::<usize>().ok()).filter(|i| *i < 22) {
+                colors.ansi[i] = Some(rgb);
+            },
+        }
+    }
+    colors
+}
+
+fn parse_vscode(input: &str) -> Result<TerminalColors, String> {
+    let json: serde_json::Value = serde_json::from_str(&strip_json_comments(input))
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+    let ui = json.get("colors").ok_or("VS Code theme has no \"colors\" section")?;
+    let get = |key: &str| ui.get(key).and_then(|v| v.as_str()).and_then(parse_color);
+
+    let mut colors = TerminalColors {
+        name: json.get("name").and_then(|v| v.as_str()).map(String::from),
+        background: get("terminal.background").or_else(|| get("editor.background")),
+        foreground: get("terminal.foreground").or_else(|| get("editor.foreground")),
+        selection: get("terminal.selectionBackground").or_else(|| get("editor.selectionBackground")),
+        ..Default::default()
+    };
+    for (i, name) in ANSI_NAMES.iter().enumerate() {
+        let capitalized = format!("{}{}", name[..1].to_uppercase(), &name[1..]);
+        colors.ansi[i] = get(&format!("terminal.ansi{}", capitalized));
+        colors.ansi[i + 8] = get(&format!("terminal.ansiBright{}", capitalized));
+    }
+    Ok(colors)
+}
+
+/// VS Code and Windows Terminal files are JSONC: drop comments and trailing commas.
+fn strip_json_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match (c, in_string) {
+            ('"', _) => {
+                in_string = !in_string;
+                out.push(c);
+            }
+            ('\\', true) => {
+                out.push(c);
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            }
+            ('/', false) if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            ('/', false) if chars.peek() == Some(&'*') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    let trailing_comma = regex::Regex::new(r",(\s*[}\]])").unwrap();
+    trailing_comma.replace_all(&out, "$1").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(scheme: &ImportedScheme, slot: Slot) -> String {
+        scheme.palette.iter().find(|(s, _)| *s == slot).unwrap().1.to_hex()
+    }
+
+    #[test]
+    fn test_parse_color_formats() {
+        assert_eq!(parse_color("#f92672"), Some(Rgb::new(0xf9, 0x26, 0x72)));
+        assert_eq!(parse_color("0xF92672"), Some(Rgb::new(0xf9, 0x26, 0x72)));
+        assert_eq!(parse_color("#fff"), Some(Rgb::new(255, 255, 255)));
+        assert_eq!(parse_color("#28283aff"), Some(Rgb::new(0x28, 0x28, 0x3a)));
+        assert_eq!(parse_color("rgb:ff/80/00"), Some(Rgb::new(255, 0x80, 0)));
+        assert_eq!(parse_color("rgb:ffff/8080/0000"), Some(Rgb::new(255, 0x80, 0)));
+        assert_eq!(parse_color("nope"), None);
+        assert_eq!(parse_color("#aééb"), None);
+        assert_eq!(parse_color("rgb:0000000/0/0"), None);
+        assert_eq!(parse_color("#aéééb"), None);
+    }
+
+    #[test]
+    fn test_import_kitty() {
+        let conf = "## name: Test Kitty\nbackground #272822\nforeground #f8f8f2\ncolor0 #272822\ncolor1 #f92672\ncolor2 #a6e22e\ncolor3 #f4bf75\ncolor4 #66d9ef\ncolor5 #ae81ff\ncolor6 #a1efe4\ncolor7 #f8f8f2\ncolor8 #75715e\ncolor15 #f9f8f5\n";
+        let scheme = import("kitty", conf, "base16").unwrap();
+
+        assert_eq!(scheme.name.as_deref(), Some("Test Kitty"));
+        assert_eq!(scheme.variant, "dark");
+        assert_eq!(scheme.palette.len(), 16);
+        assert_eq!(slot(&scheme, Slot::Base08), "#f92672");
+        assert_eq!(slot(&scheme, Slot::Base0D), "#66d9ef");
+        assert_eq!(slot(&scheme, Slot::Base03), "#75715e");
+        assert!(scheme.direct.contains(&Slot::Base08));
+        assert_eq!(scheme.inferred, vec![Slot::Base01, Slot::Base02, Slot::Base04, Slot::Base06, Slot::Base09, Slot::Base0F]);
+    }
+
+    #[test]
+    fn test_import_kitty_tinted_extended_colors() {
+        // As written by tinted-kitty: color16-color21 carry the slots ANSI has no place for
+        let conf = "background #1d2021
+foreground #d5c4a1
+selection_background #d5c4a1
+            color0 #1d2021
+color1 #fb4934
+color2 #b8bb26
+color3 #fabd2f
+color4 #83a598
+color5 #d3869b
+            color6 #8ec07c
+color7 #d5c4a1
+color8 #665c54
+color15 #fbf1c7
+            color16 #fe8019
+color17 #d65d0e
+color18 #3c3836
+color19 #504945
+color20 #bdae93
+color21 #ebdbb2
+";
+        let scheme = import("kitty", conf, "base16").unwrap();
+
+        assert_eq!(slot(&scheme, Slot::Base09), "#fe8019");
+        assert_eq!(slot(&scheme, Slot::Base0F), "#d65d0e");
+        assert_eq!(slot(&scheme, Slot::Base01), "#3c3836");
+        assert_eq!(slot(&scheme, Slot::Base02), "#504945");
+        assert_eq!(slot(&scheme, Slot::Base04), "#bdae93");
+        assert_eq!(slot(&scheme, Slot::Base06), "#ebdbb2");
+        assert!(scheme.inferred.is_empty());
+        assert_eq!(scheme.direct.len(), 16);
+
+        let xres = "*.background: #1d2021
+*.foreground: #d5c4a1
+*.color16: #fe8019
+*.color21: #ebdbb2
+*.color22: #ffffff
+";
+        let scheme = import("xresources", xres, "base16").unwrap();
+        assert_eq!(slot(&scheme, Slot::Base09), "#fe8019");
+        assert_eq!(slot(&scheme, Slot::Base06), "#ebdbb2");
+        assert!(scheme.direct.contains(&Slot::Base09) && !scheme.inferred.contains(&Slot::Base06));
+        assert!(scheme.inferred.contains(&Slot::Base0F));
+    }
+
+    #[test]
+    fn test_import_xresources_with_defines() {
+        let xres = "#define bg #1d1f21\n*.background: bg\n*.foreground: #c5c8c6\nURxvt*color1: #cc6666\n*color4: rgb:81/a2/be\n! comment\n";
+        let scheme = import("xresources", xres, "base16").unwrap();
+        assert_eq!(slot(&scheme, Slot::Base00), "#1d1f21");
+        assert_eq!(slot(&scheme, Slot::Base08), "#cc6666");
+        assert_eq!(slot(&scheme, Slot::Base0D), "#81a2be");
+        assert!(scheme.inferred.contains(&Slot::Base0B));
+    }
+
+    #[test]
+    fn test_import_alacritty() {
+        let toml = "[colors.primary]\nbackground = '#282a36'\nforeground = '#f8f8f2'\n[colors.normal]\nred = '#ff5555'\ngreen = '#50fa7b'\n[colors.bright]\nred = '#ff6e6e'\n";
+        let scheme = import("alacritty", toml, "base24").unwrap();
+        assert_eq!(scheme.palette.len(), 24);
+        assert_eq!(slot(&scheme, Slot::Base08), "#ff5555");
+        assert_eq!(slot(&scheme, Slot::Base12), "#ff6e6e");
+        assert!(scheme.direct.contains(&Slot::Base12));
+        assert!(scheme.inferred.contains(&Slot::Base14));
+    }
+
+    #[test]
+    fn test_import_windows_terminal_settings() {
+        let json = r##"{
+            // settings.json allows comments
+            "schemes": [{ "name": "Campbell", "background": "#0C0C0C", "foreground": "#CCCCCC",
+                          "red": "#C50F1F", "purple": "#881798", "brightBlack": "#767676", }]
+        }"##;
+        let scheme = import("windows-terminal", json, "base16").unwrap();
+        assert_eq!(scheme.name.as_deref(), Some("Campbell"));
+        assert_eq!(slot(&scheme, Slot::Base0E), "#881798");
+        assert_eq!(slot(&scheme, Slot::Base03), "#767676");
+    }
+
+    #[test]
+    fn test_import_vscode() {
+        let json = r##"{ "name": "Night", "colors": { "editor.background": "#011627", "editor.foreground": "#d6deeb", "terminal.ansiRed": "#EF5350" } }"##;
+        let scheme = import("vscode", json, "base16").unwrap();
+        assert_eq!(slot(&scheme, Slot::Base00), "#011627");
+        assert_eq!(slot(&scheme, Slot::Base08), "#ef5350");
+    }
+
+    #[test]
+    fn test_import_light_variant() {
+        let scheme = import("kitty", "background #fdf6e3\nforeground #657b83\n", "base16").unwrap();
+        assert_eq!(scheme.variant, "light");
+    }
+
+    #[test]
+    fn test_import_iterm() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+  <key>Ansi 1 Color</key><dict><key>Red Component</key><real>1</real><key>Green Component</key><real>0</real><key>Blue Component</key><real>0</real></dict>
+  <key>Background Color</key><dict><key>Red Component</key><real>0</real><key>Green Component</key><real>0</real><key>Blue Component</key><real>0</real></dict>
+  <key>Foreground Color</key><dict><key>Red Component</key><real>1</real><key>Green Component</key><real>1</real><key>Blue Component</key><real>1</real></dict>
+</dict></plist>"#;
+        let scheme = import("iterm", plist, "base16").unwrap();
+        assert_eq!(slot(&scheme, Slot::Base08), "#ff0000");
+        assert_eq!(slot(&scheme, Slot::Base00), "#000000");
+    }
+
+    #[test]
+    fn test_import_requires_background() {
+        assert!(import("kitty", "color1 #ff0000\n", "base16").is_err());
+        assert!(import("winamp", "", "base16").is_err());
+    }
+}
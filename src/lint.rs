@@ -0,0 +1,317 @@
+//! Validation of scheme files: parse failures, missing/extra slots, bad hex values,
+//! duplicate colours, slug collisions and system mismatches.
+
+use crate::color::Rgb;
+use crate::palette::Slot;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub path: String,
+    pub severity: Severity,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LintReport {
+    pub files: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn push(&mut self, issue: LintIssue) {
+        match issue.severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+        self.issues.push(issue);
+    }
+}
+
+/// A scheme file `lint_corpus` read, with its slug and the scheme if it parsed.
+pub struct LintedFile {
+    pub path: String,
+    pub system: &'static str,
+    /// Empty when the file name has no usable characters.
+    pub slug: String,
+    pub scheme: Option<crate::SchemeYaml>,
+}
+
+/// Lint every scheme under the given `(directory, system)` pairs. The files come back
+/// sorted by path within each directory, so the index doesn't walk, read and parse
+/// them again.
+pub fn lint_corpus(dirs: &[(&std::path::Path, &'static str)]) -> (LintReport, Vec<LintedFile>) {
+    let mut report = LintReport::default();
+    let mut files = Vec::new();
+    let mut slugs: HashMap<String, Vec<(String, &str)>> = HashMap::new();
+
+    for &(dir, system) in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        let mut paths: Vec<_> = entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("yaml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let path_str = path.to_string_lossy().to_string();
+            report.files += 1;
+
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let slug = crate::sanitize_name(&stem.to_lowercase());
+            if slug.is_empty() {
+                report.push(issue(&path_str, Severity::Error, "empty-slug",
+                    format!("File name '{}' has no usable characters after sanitizing", stem)));
+            } else {
+                slugs.entry(slug.clone()).or_default().push((path_str.clone(), system));
+            }
+
+            let scheme = match std::fs::read_to_string(&path) {
+                Ok(yaml) => {
+                    let (issues, scheme) = lint_document(&path_str, &yaml, Some(system));
+                    for issue in issues {
+                        report.push(issue);
+                    }
+                    scheme
+                }
+                Err(e) => {
+                    report.push(issue(&path_str, Severity::Error, "unreadable", e.to_string()));
+                    None
+                }
+            };
+            files.push(LintedFile { path: path_str, system, slug, scheme });
+        }
+    }
+
+    let mut collisions: Vec<_> = slugs.into_iter().filter(|(_, files)| files.len() > 1).collect();
+    collisions.sort();
+    for (slug, files) in collisions {
+        let same_system = files.iter().any(|(_, a)| files.iter().filter(|(_, b)| a == b).count() > 1);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        let (severity, kind) = match same_system {
+            true => (Severity::Error, "slug-collision"),
+            false => (Severity::Warning, "system-collision"),
+        };
+        report.push(issue(&paths.join(", "), severity, kind,
+            format!("{} files share the slug '{}'", paths.len(), slug)));
+    }
+
+    (report, files)
+}
+
+/// Lint one scheme document. `expected_system` is the directory it was found in, if any.
+pub fn lint_scheme(path: &str, yaml: &str, expected_system: Option<&str>) -> Vec<LintIssue> {
+    lint_document(path, yaml, expected_system).0
+}
+
+/// The issues in one scheme document, and the scheme with any invalid colours left out
+/// when the rest of it parses.
+fn lint_document(path: &str, yaml: &str, expected_system: Option<&str>) -> (Vec<LintIssue>, Option<crate::SchemeYaml>) {
+    let mut issues = Vec::new();
+
+    let value: serde_yaml::Value = match serde_yaml::from_str(yaml) {
+        Ok(v) => v,
+        Err(e) => {
+            issues.push(issue(path, Severity::Error, "parse", e.to_string()));
+            return (issues, None);
+        }
+    };
+    let legacy = crate::SchemeYaml::is_legacy_document(&value);
+
+    // Slot names and colours are checked on the raw document, before normalization
+    // folds case and short hex forms away
+    let raw_hex = |(k, v): (&serde_yaml::Value, &serde_yaml::Value)| {
+        let key = k.as_str()?;
+        Some((key.to_string(), crate::yaml_hex_string(key, v)))
+    };
+    let mut raw_slots: Vec<(String, Result<String, String>)> = match (legacy, &value) {
+        (false, _) => value.get("palette").and_then(|p| p.as_mapping()).into_iter().flatten()
+            .filter_map(raw_hex)
+            .collect(),
+        (true, serde_yaml::Value::Mapping(map)) => map.iter()
+            .filter_map(raw_hex)
+            .filter(|(k, _)| k.is_ascii() && k.len() == 6 && k[..4].eq_ignore_ascii_case("base") && k[4..].chars().all(|c| c.is_ascii_hexdigit()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    raw_slots.sort();
+    let mut bad_colors = Vec::new();
+    for (key, hex) in &raw_slots {
+        match hex.as_deref().map(|h| (h, Rgb::from_hex(h))) {
+            Ok((hex, Some(_))) if hex.trim_start_matches('#').len() != 6 => {
+                issues.push(issue(path, Severity::Warning, "noncanonical-hex",
+                    format!("{} is '{}'; other builders expect six hex digits", key, hex)));
+            }
+            Ok((_, Some(_))) => {}
+            Ok((hex, None)) => {
+                bad_colors.push(key.clone());
+                issues.push(issue(path, Severity::Error, "invalid-hex",
+                    format!("{} has invalid colour '{}'", key, hex)));
+            }
+            Err(message) => {
+                bad_colors.push(key.clone());
+                issues.push(issue(path, Severity::Error, "invalid-hex", message.clone()));
+            }
+        }
+    }
+
+    // Drop already-reported colours so the remaining checks still run
+    let mut cleaned = value.clone();
+    let slots = match legacy {
+        true => cleaned.as_mapping_mut(),
+        false => cleaned.get_mut("palette").and_then(|p| p.as_mapping_mut()),
+    };
+    if let Some(slots) = slots {
+        for key in &bad_colors {
+            slots.remove(key.as_str());
+        }
+    }
+    let scheme = match serde_yaml::from_value::<crate::SchemeYaml>(cleaned) {
+        Ok(s) => s,
+        Err(e) => {
+            issues.push(issue(path, Severity::Error, "parse", e.to_string()));
+            return (issues, None);
+        }
+    };
+
+    if legacy {
+        issues.push(issue(path, Severity::Warning, "legacy-format",
+            "Uses the legacy flat layout instead of a 'palette:' map".to_string()));
+    }
+
+    // Legacy files have no 'system' key; theirs is inferred from the slots present
+    let declared = match legacy {
+        true => Some(scheme.system.as_str()),
+        false => value.get("system").and_then(|v| v.as_str()),
+    };
+    let system = match (declared, expected_system) {
+        (Some(declared), Some(expected)) if declared != expected => {
+            issues.push(issue(path, Severity::Error, "system-mismatch",
+                format!("Declares system '{}' but is stored under {}", declared, expected)));
+            expected
+        }
+        (Some(declared), _) => declared,
+        (None, expected) => {
+            issues.push(issue(path, Severity::Warning, "missing-system", "No 'system' field".to_string()));
+            expected.unwrap_or("base16")
+        }
+    };
+    if !matches!(system, "base16" | "base24") {
+        issues.push(issue(path, Severity::Error, "unknown-system", format!("Unknown system '{}'", system)));
+        return (issues, Some(scheme));
+    }
+
+    let required = Slot::for_system(system);
+    for (key, _) in &raw_slots {
+        if required.iter().any(|slot| slot.name() == key) {
+            continue;
+        }
+        let message = match required.iter().find(|slot| slot.name().eq_ignore_ascii_case(key)) {
+            Some(canonical) => format!("Slot '{}' should be spelled '{}'", key, canonical),
+            None => format!("Slot '{}' is not part of {}", key, system),
+        };
+        issues.push(issue(path, Severity::Warning, "extra-slot", message));
+    }
+
+    for slot in required {
+        if !scheme.palette.contains(*slot) {
+            issues.push(issue(path, Severity::Error, "missing-slot", format!("Missing {}", slot)));
+        }
+    }
+
+    let mut by_color: HashMap<String, Vec<&str>> = HashMap::new();
+    for (slot, color) in scheme.palette.iter() {
+        by_color.entry(color.hex_digits()).or_default().push(slot.name());
+    }
+    let mut duplicates: Vec<_> = by_color.into_iter().filter(|(_, slots)| slots.len() > 1).collect();
+    duplicates.sort();
+    for (hex, slots) in duplicates {
+        issues.push(issue(path, Severity::Warning, "duplicate-color",
+            format!("#{} is used by {}", hex, slots.join(", "))));
+    }
+
+    (issues, Some(scheme))
+}
+
+fn issue(path: &str, severity: Severity, kind: &'static str, message: String) -> LintIssue {
+    LintIssue { path: path.to_string(), severity, kind, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(issues: &[LintIssue]) -> Vec<&'static str> {
+        issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_lint_clean_scheme() {
+        let palette: String = Slot::for_system("base16").iter().enumerate()
+            .map(|(i, s)| format!("  {}: \"#0000{:02x}\"\n", s, i))
+            .collect();
+        let yaml = format!("system: base16\nname: Clean\nauthor: Me\npalette:\n{}", palette);
+        assert!(lint_scheme("clean.yaml", &yaml, Some("base16")).is_empty());
+    }
+
+    #[test]
+    fn test_lint_reports_problems() {
+        let yaml = "system: base24\nname: Broken\nauthor: Me\npalette:\n  base00: \"#000000\"\n  base01: \"#000000\"\n  base0a: \"#12345\"\n";
+        let issues = lint_scheme("broken.yaml", yaml, Some("base16"));
+        let kinds = kinds(&issues);
+        assert!(kinds.contains(&"system-mismatch"));
+        assert!(kinds.contains(&"invalid-hex"));
+        assert!(kinds.contains(&"missing-slot"));
+        assert!(kinds.contains(&"extra-slot"));
+        assert!(kinds.contains(&"duplicate-color"));
+        assert!(issues.iter().any(|i| i.message.contains("should be spelled 'base0A'")));
+    }
+
+    #[test]
+    fn test_lint_legacy_scheme() {
+        let yaml = "scheme: Old\nauthor: Me\nbase00: \"000000\"\n";
+        let issues = lint_scheme("old.yaml", yaml, Some("base16"));
+        let kinds = kinds(&issues);
+        assert!(kinds.contains(&"legacy-format"));
+        assert!(kinds.contains(&"missing-slot"));
+        assert!(!kinds.contains(&"missing-system"));
+
+        // Unquoted 123e45 is a float to YAML; the hex digits are lost
+        let yaml = "scheme: Old\nauthor: Me\nbase00: 123e45\n";
+        let issues = lint_scheme("old.yaml", yaml, Some("base16"));
+        let invalid = issues.iter().find(|i| i.kind == "invalid-hex").unwrap();
+        assert!(invalid.message.contains("quote the hex colour"), "{}", invalid.message);
+    }
+
+    #[test]
+    fn test_lint_parse_failure() {
+        let issues = lint_scheme("bad.yaml", "name: [unclosed", None);
+        assert_eq!(kinds(&issues), vec!["parse"]);
+        let issues = lint_scheme("no-palette.yaml", "name: X\nauthor: Y\n", None);
+        assert_eq!(kinds(&issues), vec!["parse"]);
+        // A bad colour doesn't hide the other reasons the file won't load
+        let issues = lint_scheme("no-author.yaml", "system: base16\nname: X\npalette:\n  base00: \"#zzzzzz\"\n", None);
+        assert_eq!(kinds(&issues), vec!["invalid-hex", "parse"]);
+        assert!(issues[1].message.contains("author"));
+    }
+}
@@ -1,10 +1,23 @@
+mod color;
+mod from_image;
+mod highlight;
+mod aliases;
+mod archive;
+mod hosts;
+mod import;
+mod lint;
+mod matrix;
+mod palette;
+mod render;
+mod search;
+
 use axum::{
     Router,
-    routing::get,
-    extract::{Path, Query},
+    routing::{get, post},
+    extract::{Extension, Path, Query, RawQuery},
     response::{IntoResponse, Response, Redirect},
     http::{StatusCode, HeaderMap, HeaderValue, header},
-    body::Body,
+    body::{Body, Bytes},
 };
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::limit::RequestBodyLimitLayer;
@@ -12,17 +25,26 @@ use mustache::MapBuilder;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
 use tokio::fs::File;
+use futures_util::StreamExt;
 use tokio_util::io::ReaderStream;
 use resvg::usvg;
 use tiny_skia::Pixmap;
+use color::Rgb;
+use palette::{Palette, Slot};
+
+/// Request body limit for uploads and batches; everything else stays at 1KB.
+const UPLOAD_LIMIT: usize = 16 * 1024 * 1024;
 
 static SCHEME_INDEX: Lazy<SchemeIndex> = Lazy::new(|| {
     SchemeIndex::load().expect("Failed to load scheme index")
 });
 
+static HOST_PROFILES: Lazy<hosts::HostProfiles> = Lazy::new(hosts::HostProfiles::load);
+
 static TEMPLATE_INDEX: Lazy<TemplateIndex> = Lazy::new(|| {
     TemplateIndex::load().expect("Failed to load template index")
 });
@@ -37,77 +59,282 @@ static SCHEME_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
         .expect("Failed to load scheme template")
 });
 
+static EDIT_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
+    mustache::compile_path("templates/edit.html.mustache")
+        .expect("Failed to load edit template")
+});
+
+static LINT_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
+    mustache::compile_path("templates/lint.html.mustache")
+        .expect("Failed to load lint template")
+});
+
+static CHOICES_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
+    mustache::compile_path("templates/choices.html.mustache")
+        .expect("Failed to load choices template")
+});
+
+static PREVIEW_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
+    mustache::compile_path("templates/preview.html.mustache")
+        .expect("Failed to load preview template")
+});
+
 static OG_IMAGE_TEMPLATE: Lazy<mustache::Template> = Lazy::new(|| {
     mustache::compile_path("templates/og.svg.mustache")
         .expect("Failed to load OG image template")
 });
 
+/// Compiled repo templates by path, shared by every render. Template files don't change
+/// while the server runs.
+static COMPILED_TEMPLATES: Lazy<RwLock<HashMap<String, Arc<CompiledTemplate>>>> = Lazy::new(Default::default);
+
+/// `{{ name }}` placeholders in a config.yaml `filename` pattern.
+static FILENAME_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([\w-]+)\s*\}\}").unwrap());
+
+/// Most (scheme, template) pairs one `POST /--batch` may ask for.
+const MAX_BATCH_ITEMS: usize = 500;
+
+/// System a bare slug resolves to when it exists in both base16 and base24.
+const DEFAULT_SYSTEM: &str = "base16";
+
+/// Jaro-Winkler similarity a misspelled scheme needs before we redirect to it.
+const FUZZY_THRESHOLD: f64 = 0.8;
+/// Runners-up within this much of the best match make a guess ambiguous.
+const FUZZY_MARGIN: f64 = 0.02;
+/// Lowest similarity still offered as a "did you mean" suggestion.
+const SUGGEST_THRESHOLD: f64 = 0.6;
+const MAX_SUGGESTIONS: usize = 5;
+
 #[derive(Debug)]
 struct SchemeInfo {
     name: String,
     path: String,
     system: String,
+    /// URL path: the slug, or `system/slug` when the slug exists in both systems.
+    id: String,
+}
+
+impl SchemeInfo {
+    /// URL path on a host: the bare slug when the host serves a single system.
+    fn path_on(&self, profile: &hosts::HostProfile) -> &str {
+        match profile.single_system() {
+            Some(_) => &self.name,
+            None => &self.id,
+        }
+    }
 }
 
 struct SchemeIndex {
+    /// Keyed by `SchemeInfo::id`.
     schemes: HashMap<String, SchemeInfo>,
+    /// Slug to the ids of the schemes it names, one per system.
+    by_slug: HashMap<String, Vec<String>>,
+    /// Ids ordered by slug, then system.
     names_sorted: Vec<String>,
     color_sorted: Vec<String>,
+    lint: lint::LintReport,
+    aliases: aliases::Aliases,
+    search: search::NameIndex,
+}
+
+/// Set on the `/base16/...` and `/base24/...` routes to pin scheme lookups to a system.
+#[derive(Debug, Clone, Copy)]
+struct SystemPin(&'static str);
+
+/// Why a `{scheme}` or `{template}` path segment didn't resolve directly. Renames and
+/// removals only apply to schemes.
+enum Miss {
+    Redirect(String),
+    /// Single clear fuzzy match; temporary so caches don't pin a wrong guess.
+    Guess(String),
+    /// Renamed upstream; 301 so old links in dotfiles and bookmarks get updated.
+    Renamed(String),
+    /// Removed upstream, with the path of a suggested successor if there is one.
+    Gone { name: String, successor: Option<String> },
+    /// Several fuzzy matches scored about the same: 300 Multiple Choices.
+    Ambiguous(Suggestions),
+    NotFound(Suggestions),
+}
+
+#[derive(Debug, Serialize)]
+struct Choice {
+    name: String,
+    /// Set for schemes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    url: String,
+    score: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChoiceFormat {
+    Text,
+    Json,
+    Html,
+}
+
+/// Candidates for a name that didn't match, rendered for the client's Accept header.
+struct Suggestions {
+    /// "Scheme" or "Template".
+    what: &'static str,
+    query: String,
+    choices: Vec<Choice>,
+    format: ChoiceFormat,
+    site_name: String,
+}
+
+impl Suggestions {
+    fn new(what: &'static str, query: &str, choices: Vec<Choice>, headers: &HeaderMap) -> Self {
+        let accept = headers.get("accept").and_then(|v| v.to_str().ok()).unwrap_or("");
+        let format = match accept {
+            a if a.contains("application/json") => ChoiceFormat::Json,
+            a if a.contains("text/html") => ChoiceFormat::Html,
+            _ => ChoiceFormat::Text,
+        };
+        let site_name = HOST_PROFILES.for_headers(headers).host.clone();
+        Suggestions { what, query: query.to_string(), choices, format, site_name }
+    }
+
+    fn respond(self, status: StatusCode, title: &str) -> Response {
+        let (content_type, body) = match self.format {
+            ChoiceFormat::Json => ("application/json", serde_json::to_string_pretty(&self.choices).unwrap()),
+            ChoiceFormat::Html => {
+                let data = MapBuilder::new()
+                    .insert_str("title", title)
+                    .insert_str("query", &self.query)
+                    .insert_str("what", self.what.to_lowercase())
+                    .insert_str("site-name", &self.site_name)
+                    .insert_bool("has-choices", !self.choices.is_empty())
+                    .insert_vec("choices", |mut vec| {
+                        for choice in &self.choices {
+                            vec = vec.push_map(|map| {
+                                map.insert_str("name", &choice.name)
+                                   .insert_str("system", choice.system.as_deref().unwrap_or(""))
+                                   .insert_str("url", &choice.url)
+                                   .insert_str("score", format!("{:.2}", choice.score))
+                            });
+                        }
+                        vec
+                    })
+                    .build();
+                match CHOICES_TEMPLATE.render_data_to_string(&data) {
+                    Ok(html) => ("text/html; charset=utf-8", html),
+                    Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render template").into_response(),
+                }
+            }
+            ChoiceFormat::Text => {
+                let mut text = format!("{}\n", title);
+                if !self.choices.is_empty() {
+                    text.push_str("\nDid you mean:\n");
+                    for choice in &self.choices {
+                        let system = choice.system.as_ref().map(|s| format!("{}, ", s)).unwrap_or_default();
+                        text.push_str(&format!("  {}  ({}{:.2})\n", choice.url, system, choice.score));
+                    }
+                }
+                ("text/plain; charset=utf-8", text)
+            }
+        };
+        Response::builder()
+            .status(status)
+            .header("content-type", content_type)
+            .header("vary", "Accept")
+            .body(Body::from(body))
+            .unwrap()
+    }
+}
+
+impl IntoResponse for Miss {
+    fn into_response(self) -> Response {
+        match self {
+            Miss::Redirect(location) => Redirect::permanent(&location).into_response(),
+            Miss::Guess(location) => (
+                StatusCode::TEMPORARY_REDIRECT,
+                [(header::LOCATION, location), (header::CACHE_CONTROL, "no-cache".to_string())],
+            ).into_response(),
+            Miss::Renamed(location) => {
+                (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response()
+            }
+            Miss::Gone { name, successor: Some(successor) } => (
+                StatusCode::GONE,
+                [(header::LINK, format!("<{}>; rel=\"successor-version\"", successor))],
+                format!("Scheme '{}' has been removed. Try {}", name, successor),
+            ).into_response(),
+            Miss::Gone { name, successor: None } => {
+                (StatusCode::GONE, format!("Scheme '{}' has been removed", name)).into_response()
+            }
+            Miss::Ambiguous(suggestions) => {
+                let title = format!("{} '{}' matches several {}s", suggestions.what, suggestions.query, suggestions.what.to_lowercase());
+                let mut response = suggestions.respond(StatusCode::MULTIPLE_CHOICES, &title);
+                response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+                response
+            }
+            Miss::NotFound(suggestions) => {
+                let title = format!("{} '{}' not found", suggestions.what, suggestions.query);
+                suggestions.respond(StatusCode::NOT_FOUND, &title)
+            }
+        }
+    }
 }
 
 impl SchemeIndex {
     fn load() -> std::io::Result<Self> {
-        let mut schemes = HashMap::new();
+        // One pass reads, parses and lints every file; the index is built from what it read
         let base16_dir = std::path::Path::new("data/schemes/base16");
         let base24_dir = std::path::Path::new("data/schemes/base24");
+        let (lint, files) = lint::lint_corpus(&[(base16_dir, "base16"), (base24_dir, "base24")]);
+        for issue in lint.issues.iter().filter(|i| i.severity == lint::Severity::Error) {
+            tracing::warn!("{}: {} ({})", issue.path, issue.message, issue.kind);
+        }
+        if lint.errors + lint.warnings > 0 {
+            tracing::warn!("Scheme lint found {} errors and {} warnings, see /--lint", lint.errors, lint.warnings);
+        }
 
-        for (dir, system) in [(base16_dir, "base16"), (base24_dir, "base24")] {
-            if let Ok(entries) = std::fs::read_dir(dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("yaml")
-                        && let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                            let name = sanitize_name(&stem.to_lowercase());
-                            if name.is_empty() {
-                                continue;
-                            }
-                            schemes.insert(name.clone(), SchemeInfo {
-                                name: name.clone(),
-                                path: path.to_string_lossy().to_string(),
-                                system: system.to_string(),
-                            });
-                        }
-                }
+        // Files come sorted by path, so of those sharing a slug and system the last one
+        // wins on every filesystem (lint reports them)
+        let mut found: HashMap<(String, &str), String> = HashMap::new();
+        let mut parsed: HashMap<String, SchemeYaml> = HashMap::new();
+        for file in files.into_iter().filter(|file| !file.slug.is_empty()) {
+            found.insert((file.slug, file.system), file.path.clone());
+            if let Some(scheme) = file.scheme {
+                parsed.insert(file.path, scheme);
             }
         }
 
+        let mut systems_per_slug: HashMap<String, usize> = HashMap::new();
+        for (name, _) in found.keys() {
+            *systems_per_slug.entry(name.clone()).or_default() += 1;
+        }
+        let mut schemes = HashMap::new();
+        let mut by_slug: HashMap<String, Vec<String>> = HashMap::new();
+        for ((name, system), path) in found {
+            let id = match systems_per_slug[&name] {
+                1 => name.clone(),
+                _ => format!("{}/{}", system, name),
+            };
+            by_slug.entry(name.clone()).or_default().push(id.clone());
+            schemes.insert(id.clone(), SchemeInfo { name, path, system: system.to_string(), id });
+        }
+
         let mut names_sorted: Vec<String> = schemes.keys().cloned().collect();
-        names_sorted.sort();
+        names_sorted.sort_by_key(|id| (&schemes[id].name, &schemes[id].system));
 
-        let color_sorted = Self::compute_color_order(&schemes);
+        let color_sorted = Self::compute_color_order(&schemes, &parsed);
 
         tracing::info!("Loaded {} schemes into index", schemes.len());
 
-        Ok(SchemeIndex { schemes, names_sorted, color_sorted })
+        let search = search::NameIndex::new(by_slug.keys());
+        let mut index = SchemeIndex { schemes, by_slug, names_sorted, color_sorted, lint, aliases: Default::default(), search };
+        let slugs = index.by_slug.keys().cloned().collect();
+        index.aliases = aliases::Aliases::load(&slugs);
+        Ok(index)
     }
 
-    fn compute_color_order(schemes: &HashMap<String, SchemeInfo>) -> Vec<String> {
-        let color_keys = [
-            "base00", "base01", "base02", "base03", "base04", "base05", "base06", "base07",
-            "base08", "base09", "base0A", "base0B", "base0C", "base0D", "base0E", "base0F",
-        ];
-
-        let scheme_to_vector = |palette: &HashMap<String, String>| -> Vec<f64> {
-            color_keys.iter().flat_map(|key| {
-                let hex = palette.get(*key).map(|s| s.as_str()).unwrap_or("#000000").trim_start_matches('#');
-                if hex.len() >= 6 {
-                    let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0) as f64;
-                    let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0) as f64;
-                    let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0) as f64;
-                    [r, g, b]
-                } else {
-                    [0.0, 0.0, 0.0]
-                }
+    /// `parsed` holds the scheme files the lint pass read, by path.
+    fn compute_color_order(schemes: &HashMap<String, SchemeInfo>, parsed: &HashMap<String, SchemeYaml>) -> Vec<String> {
+        let scheme_to_vector = |palette: &Palette| -> Vec<f64> {
+            Slot::for_system("base16").iter().flat_map(|slot| {
+                let c = palette.get(*slot).unwrap_or(Rgb::new(0, 0, 0));
+                [c.r as f64, c.g as f64, c.b as f64]
             }).collect()
         };
 
@@ -115,36 +342,23 @@ impl SchemeIndex {
             a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
         };
 
-        let is_grey_scheme = |palette: &HashMap<String, String>| -> bool {
-            let accent_keys = ["base08", "base09", "base0A", "base0B", "base0C", "base0D", "base0E", "base0F"];
-            let mut grey_count = 0;
-            for key in accent_keys {
-                if let Some(hex) = palette.get(key) {
-                    let hex = hex.trim_start_matches('#');
-                    if hex.len() >= 6 {
-                        let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0) as f64 / 255.0;
-                        let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0) as f64 / 255.0;
-                        let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0) as f64 / 255.0;
-                        let max = r.max(g).max(b);
-                        let min = r.min(g).min(b);
-                        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
-                        if saturation < 0.2 {
-                            grey_count += 1;
-                        }
-                    }
-                }
-            }
+        let is_grey_scheme = |palette: &Palette| -> bool {
+            let grey_count = Slot::for_system("base16")[8..].iter()
+                .filter_map(|slot| palette.get(*slot))
+                .filter(|c| {
+                    let max = c.r.max(c.g).max(c.b) as f64;
+                    let min = c.r.min(c.g).min(c.b) as f64;
+                    let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+                    saturation < 0.2
+                })
+                .count();
             grey_count >= 5
         };
 
-        // Load scheme data and compute color vectors
-        let mut schemes_with_data: Vec<(String, HashMap<String, String>)> = schemes
+        // Compute color vectors from the parsed scheme data
+        let mut schemes_with_data: Vec<(String, Palette)> = schemes
             .iter()
-            .filter_map(|(name, info)| {
-                let yaml_str = std::fs::read_to_string(&info.path).ok()?;
-                let scheme_data: SchemeYaml = serde_yaml::from_str(&yaml_str).ok()?;
-                Some((name.clone(), scheme_data.palette))
-            })
+            .filter_map(|(name, info)| Some((name.clone(), parsed.get(&info.path)?.palette.clone())))
             .collect();
 
         // Sort alphabetically first for consistent starting point
@@ -185,28 +399,51 @@ impl SchemeIndex {
         order.iter().map(|&i| schemes_with_data[i].0.clone()).collect()
     }
 
-    fn get_neighbors(&self, name: &str, by_color: bool) -> (Option<&str>, Option<&str>) {
-        let list = if by_color { &self.color_sorted } else { &self.names_sorted };
-        let Some(i) = list.iter().position(|n| n == name) else {
+    /// Schemes the host serves, by slug then system.
+    fn served<'a>(&'a self, profile: &'a hosts::HostProfile) -> impl Iterator<Item = &'a SchemeInfo> + 'a {
+        self.names_sorted.iter()
+            .map(|id| &self.schemes[id])
+            .filter(|info| profile.serves(&info.system))
+    }
+
+    fn get_neighbors(&self, name: &str, by_color: bool, profile: &hosts::HostProfile) -> (Option<&str>, Option<&str>) {
+        let list: Vec<&SchemeInfo> = if by_color { &self.color_sorted } else { &self.names_sorted }
+            .iter()
+            .map(|id| &self.schemes[id])
+            .filter(|info| profile.serves(&info.system))
+            .collect();
+        let Some(i) = list.iter().position(|info| info.id == name) else {
             return (None, None);
         };
-        let prev = i.checked_sub(1).map(|j| list[j].as_str());
-        let next = list.get(i + 1).map(|s| s.as_str());
+        let prev = i.checked_sub(1).map(|j| list[j].path_on(profile));
+        let next = list.get(i + 1).map(|info| info.path_on(profile));
         (prev, next)
     }
 
     fn find_exact(&self, name: &str) -> Option<&SchemeInfo> {
-        self.schemes.get(&name.to_lowercase())
+        self.find(name, None, DEFAULT_SYSTEM)
     }
 
-    fn find_fuzzy(&self, query: &str, threshold: f64) -> Option<&SchemeInfo> {
-        let query_lower = query.to_lowercase();
-        self.names_sorted
+    /// Look up a slug in `system` if given; otherwise a slug that exists in both
+    /// systems resolves to `prefer`.
+    fn find(&self, name: &str, system: Option<&str>, prefer: &str) -> Option<&SchemeInfo> {
+        let infos: Vec<&SchemeInfo> = self.by_slug.get(&name.to_lowercase())?
             .iter()
-            .map(|name| (name, strsim::jaro_winkler(&query_lower, name)))
+            .map(|id| &self.schemes[id])
+            .collect();
+        match system {
+            Some(system) => infos.into_iter().find(|info| info.system == system),
+            None => infos.iter().find(|info| info.system == prefer).or(infos.first()).copied(),
+        }
+    }
+
+    /// Slugs scoring at least `threshold`, best first (ties by slug).
+    fn fuzzy_slugs(&self, query: &str, threshold: f64, system: Option<&str>) -> Vec<(&str, f64)> {
+        self.search.similar(query)
+            .into_iter()
             .filter(|(_, sim)| *sim >= threshold)
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .and_then(|(name, _)| self.schemes.get(name))
+            .filter(|(slug, _)| system.is_none_or(|s| self.by_slug[*slug].iter().any(|id| self.schemes[id].system == s)))
+            .collect()
     }
 }
 
@@ -215,11 +452,117 @@ struct TemplateInfo {
     name: String,
     base16_path: Option<String>,
     base24_path: Option<String>,
-    extension: String,
-    _repo: String,
+    /// config.yaml `filename` patterns for each slot.
+    base16_filename: Option<String>,
+    base24_filename: Option<String>,
+    /// config.yaml `escape` modes for each slot.
+    base16_escape: Option<render::Escape>,
+    base24_escape: Option<render::Escape>,
+    /// Repos the paths come from.
+    repos: Vec<String>,
+}
+
+/// One template as found in a repo's config.yaml, before keys are reconciled across repos.
+#[derive(Debug)]
+struct TemplateSource {
+    key: String,
+    repo: String,
+    /// "base16", "base24" or "both".
+    variant: &'static str,
+    path: String,
+    filename: Option<String>,
+    escape: Option<render::Escape>,
+}
+
+impl TemplateSource {
+    fn provides(&self, system: &str) -> bool {
+        self.variant == system || self.variant == "both"
+    }
+}
+
+/// One entry of a repo's config.yaml as it goes into archives, whether or not it has a
+/// key of its own.
+#[derive(Debug)]
+struct TemplateFile {
+    name: String,
+    /// "base16", "base24" or "both".
+    variant: &'static str,
+    path: String,
+    filename: Option<String>,
+    escape: Option<render::Escape>,
+}
+
+impl TemplateFile {
+    fn provides(&self, system: &str) -> bool {
+        self.variant == system || self.variant == "both"
+    }
+
+    /// Path inside the archive, from the entry's `filename` pattern.
+    fn install_path(&self, slug: &str, system: &str) -> String {
+        self.filename.as_deref()
+            .map(|pattern| render_filename(pattern, system, slug))
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| format!("{}.{}", slug, sanitize_name(&self.name)))
+    }
+
+    fn escape(&self, slug: &str, system: &str) -> render::Escape {
+        self.escape.unwrap_or_else(|| render::Escape::for_filename(&self.install_path(slug, system)))
+    }
 }
 
 impl TemplateInfo {
+    fn from_sources<'a>(name: String, sources: impl Iterator<Item = &'a TemplateSource>) -> Self {
+        let mut info = TemplateInfo {
+            name,
+            base16_path: None,
+            base24_path: None,
+            base16_filename: None,
+            base24_filename: None,
+            base16_escape: None,
+            base24_escape: None,
+            repos: Vec::new(),
+        };
+        for source in sources {
+            info.add(source);
+        }
+        info
+    }
+
+    fn add(&mut self, source: &TemplateSource) {
+        for system in ["base16", "base24"] {
+            if source.provides(system) {
+                self.fill(system, source);
+            }
+        }
+    }
+
+    /// Take `system`'s slot from `source`, leaving the other slot alone.
+    fn fill(&mut self, system: &str, source: &TemplateSource) {
+        match system {
+            "base24" => {
+                self.base24_path = Some(source.path.clone());
+                self.base24_filename = source.filename.clone();
+                self.base24_escape = source.escape;
+            }
+            _ => {
+                self.base16_path = Some(source.path.clone());
+                self.base16_filename = source.filename.clone();
+                self.base16_escape = source.escape;
+            }
+        }
+        if !self.repos.contains(&source.repo) {
+            self.repos.push(source.repo.clone());
+        }
+    }
+
+    /// The template written for `system` itself, without the base16 fallback.
+    fn path_for_slot(&self, system: &str) -> Option<&str> {
+        match system {
+            "base24" => self.base24_path.as_deref(),
+            _ => self.base16_path.as_deref(),
+        }
+    }
+
     fn path_for_system(&self, system: &str) -> Option<&str> {
         match system {
             // base24 schemes can use base24 templates, or fallback to base16 templates
@@ -229,11 +572,114 @@ impl TemplateInfo {
         }
     }
 
-    fn download_filename(&self, slug: &str) -> String {
-        if self.extension.is_empty() {
-            format!("{}.{}", slug, self.name)
-        } else {
-            format!("{}{}", slug, self.extension)
+    /// Where the builder would write the output for a `system` scheme, relative to the
+    /// app's config directory, when the repo's config says.
+    fn install_path(&self, slug: &str, system: &str) -> Option<String> {
+        let pattern = match system {
+            "base24" if self.base24_path.is_some() => self.base24_filename.as_deref(),
+            _ => self.base16_filename.as_deref(),
+        }?;
+        Some(render_filename(pattern, system, slug)).filter(|path| !path.is_empty())
+    }
+
+    /// How values are escaped in the `system` output: as the repo's config.yaml declares,
+    /// otherwise by the output's extension.
+    fn escape(&self, slug: &str, system: &str) -> render::Escape {
+        let declared = match system {
+            "base24" if self.base24_path.is_some() => self.base24_escape,
+            _ => self.base16_escape,
+        };
+        declared.unwrap_or_else(|| render::Escape::for_filename(&self.download_filename(slug, system)))
+    }
+
+    fn download_filename(&self, slug: &str, system: &str) -> String {
+        match self.install_path(slug, system) {
+            Some(path) => path.rsplit('/').next().unwrap_or(&path).to_string(),
+            // Key only, without a `{repo}:` qualifier
+            None => format!("{}.{}", slug, self.name.rsplit(':').next().unwrap_or(&self.name)),
+        }
+    }
+}
+
+/// Content type for a rendered file, by extension. Types a browser would execute or
+/// lay out (HTML, SVG) stay text/plain.
+fn content_type_for(filename: &str) -> &'static str {
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "json" | "jsonc" => "application/json; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "xml" | "plist" | "tmtheme" | "xaml" => "application/xml; charset=utf-8",
+        "yaml" | "yml" => "application/yaml; charset=utf-8",
+        "toml" => "application/toml; charset=utf-8",
+        "lua" => "text/x-lua; charset=utf-8",
+        "sh" | "bash" | "zsh" => "text/x-shellscript; charset=utf-8",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+/// Key and variant for one config.yaml entry, or None when it isn't served. The variant
+/// comes from `supported-systems`; configs that don't declare it fall back to guessing
+/// from the template name (tinted-*) or the `base24-` repo prefix. A declared entry whose
+/// name gives no key is served as `{repo}-{name}`. Entries where the guess disagrees
+/// with the declaration, that find no key, or that declare only systems this server
+/// doesn't render are reported in `notes`.
+fn classify_template(
+    repo_name: &str,
+    short_repo: &str,
+    template_name: &str,
+    template_count: usize,
+    entry: &TemplateConfigEntry,
+    notes: &mut Vec<lint::LintIssue>,
+) -> Option<(String, &'static str)> {
+    let guessed = match repo_name.strip_prefix("tinted-") {
+        Some(tinted_short) => parse_tinted_template_name(template_name, tinted_short)
+            .map(|(base_name, variant)| (sanitize_name(&base_name), variant)),
+        None => {
+            let key = match (template_count, template_name, short_repo) {
+                (1, _, _) | (_, "default", _) => short_repo.to_string(),
+                (_, name, "css-etc" | "styles") => name.to_string(),
+                (_, name, _) => format!("{}-{}", short_repo, name),
+            };
+            let variant = match repo_name.starts_with("base24-") {
+                true => "base24",
+                false => "base16",
+            };
+            Some((key, variant))
+        }
+    };
+
+    let mut note = |message: String| notes.push(lint::LintIssue {
+        path: format!("{}/templates/config.yaml", repo_name),
+        severity: lint::Severity::Warning,
+        kind: "template-classification",
+        message,
+    });
+    let Some(systems) = &entry.supported_systems else {
+        if guessed.is_none() {
+            note(format!("Template '{}' doesn't declare supported-systems and its name matches no known pattern; it is skipped", template_name));
+        }
+        return guessed;
+    };
+    let Some(declared) = entry.declared_variant() else {
+        note(format!("Template '{}' declares supported-systems {}, none of which this server renders; it is skipped",
+            template_name, systems.join(", ")));
+        return None;
+    };
+    match guessed {
+        Some((key, variant)) => {
+            if variant != declared {
+                note(format!("Template '{}' looks like {} by its name but declares supported-systems {}; serving it as {}",
+                    template_name, variant, systems.join(", "), declared));
+            }
+            Some((key, declared))
+        }
+        None => {
+            let short = repo_name.strip_prefix("tinted-").unwrap_or(short_repo);
+            let key = sanitize_name(&format!("{}-{}", short, template_name.replace('.', "-")));
+            note(format!("Template '{}' declares supported-systems {} but its name matches no known pattern; serving it as {}",
+                template_name, systems.join(", "), key));
+            Some((key, declared))
         }
     }
 }
@@ -248,9 +694,8 @@ fn parse_tinted_template_name(name: &str, tinted_short: &str) -> Option<(String,
     }
 
     // tinted-vim style: "tinted-{short}" supports both
-    match name == format!("tinted-{}", tinted_short) {
-        true => return Some((tinted_short.to_string(), "both")),
-        _ => {}
+    if name == format!("tinted-{}", tinted_short) {
+        return Some((tinted_short.to_string(), "both"));
     }
 
     // Suffix patterns (ordered longest first)
@@ -288,131 +733,357 @@ fn parse_tinted_template_name(name: &str, tinted_short: &str) -> Option<(String,
     None
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct TemplateConfigEntry {
     #[serde(default)]
     extension: Option<String>,
     #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
     filename: Option<String>,
+    #[serde(default, rename = "supported-systems")]
+    supported_systems: Option<Vec<String>>,
+    /// How `{{name}}` values are escaped: none, html, json, xml or shell.
+    #[serde(default)]
+    escape: Option<String>,
 }
 
 impl TemplateConfigEntry {
-    fn extension(&self) -> String {
-        if let Some(ext) = &self.extension {
-            return ext.clone();
+    /// The variant `supported-systems` declares, if it names base16 or base24.
+    fn declared_variant(&self) -> Option<&'static str> {
+        let systems = self.supported_systems.as_ref()?;
+        let lists = |system: &str| systems.iter().any(|s| s.eq_ignore_ascii_case(system));
+        match (lists("base16"), lists("base24")) {
+            (true, true) => Some("both"),
+            (true, false) => Some("base16"),
+            (false, true) => Some("base24"),
+            (false, false) => None,
         }
+    }
+
+    /// The `filename` pattern, or its equivalent for older configs that give `output` and
+    /// `extension` instead.
+    fn filename_pattern(&self) -> Option<String> {
         if let Some(filename) = &self.filename {
-            let clean = filename.replace("{{ ", "").replace(" }}", "").replace("{{", "").replace("}}", "");
-            if let Some(dot_pos) = clean.rfind('.') {
-                return clean[dot_pos..].to_string();
-            }
+            return Some(filename.clone());
         }
-        String::new()
+        let extension = self.extension.as_deref()?;
+        let dir = self.output.as_deref().map(|output| format!("{}/", output.trim_end_matches('/'))).unwrap_or_default();
+        Some(format!("{}{{{{ scheme-system }}}}-{{{{ scheme-slug }}}}{}", dir, extension))
     }
 }
 
+/// Fill a config.yaml `filename` pattern's `{{ scheme-system }}` and `{{ scheme-slug }}`.
+/// The result is always relative: empty, `.` and `..` segments are dropped.
+fn render_filename(pattern: &str, system: &str, slug: &str) -> String {
+    let rendered = FILENAME_PLACEHOLDER.replace_all(pattern, |caps: &regex::Captures| match &caps[1] {
+        "scheme-system" => system.to_string(),
+        "scheme-slug" => slug.to_string(),
+        _ => String::new(),
+    });
+    rendered.split(['/', '\\'])
+        .filter(|segment| !matches!(*segment, "" | "." | ".."))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Other names people use for templates, by canonical key. Entries for keys that aren't
+/// loaded are ignored.
+const TEMPLATE_ALIASES: &[(&str, &[&str])] = &[
+    ("nvim", &["neovim"]),
+    ("vscode", &["vscode-theme", "code", "visual-studio-code"]),
+    ("shell", &["bash", "zsh", "sh"]),
+    ("windows-terminal", &["wt", "windowsterminal"]),
+    ("iterm2", &["iterm"]),
+    ("xresources", &["xres", "x11"]),
+];
+
 struct TemplateIndex {
     templates: HashMap<String, TemplateInfo>,
+    /// Every repo's version of every key, as `{repo}:{key}`.
+    qualified: HashMap<String, TemplateInfo>,
+    /// Alias -> key, from `TEMPLATE_ALIASES` and the `base16-`/`base24-`/`tinted-` repo
+    /// names each key comes from.
+    aliases: HashMap<String, String>,
+    /// Keys and aliases.
+    search: search::NameIndex,
+    /// Key collisions between repos, and config entries the name heuristics get wrong.
+    lint: lint::LintReport,
+    /// Every config.yaml entry by repo, for archives.
+    files: HashMap<String, Vec<TemplateFile>>,
 }
 
 impl TemplateIndex {
     fn load() -> std::io::Result<Self> {
-        let mut templates = HashMap::new();
+        let mut sources = Vec::new();
+        let mut notes = Vec::new();
+        let mut files: HashMap<String, Vec<TemplateFile>> = HashMap::new();
         let templates_dir = std::path::Path::new("data/templates");
 
-        if let Ok(entries) = std::fs::read_dir(templates_dir) {
-            for entry in entries.flatten() {
-                let repo_path = entry.path();
-                if !repo_path.is_dir() {
-                    continue;
-                }
-                let repo_name = repo_path.file_name().unwrap().to_str().unwrap();
-                let config_path = repo_path.join("templates/config.yaml");
-
-                if let Ok(config_str) = std::fs::read_to_string(&config_path)
-                    && let Ok(config) = serde_yaml::from_str::<HashMap<String, TemplateConfigEntry>>(&config_str) {
-                        let short_repo = sanitize_name(
-                            repo_name
-                                .trim_start_matches("base16-")
-                                .trim_start_matches("base24-")
-                        );
-                        if short_repo.is_empty() {
-                            continue;
-                        }
+        // Sorted so that reconciling keys doesn't depend on directory order
+        let mut repo_paths: Vec<_> = std::fs::read_dir(templates_dir).into_iter().flatten().flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        repo_paths.sort();
+
+        for repo_path in repo_paths {
+            let repo_name = repo_path.file_name().unwrap().to_str().unwrap();
+            let config_path = repo_path.join("templates/config.yaml");
+
+            if let Ok(config_str) = std::fs::read_to_string(&config_path)
+                && let Ok(config) = serde_yaml::from_str::<BTreeMap<String, TemplateConfigEntry>>(&config_str) {
+                    let short_repo = sanitize_name(
+                        repo_name
+                            .trim_start_matches("base16-")
+                            .trim_start_matches("base24-")
+                    );
+                    if short_repo.is_empty() {
+                        continue;
+                    }
 
-                        let is_tinted = repo_name.starts_with("tinted-");
-                        let tinted_short = repo_name.strip_prefix("tinted-").unwrap_or("");
-                        let template_count = config.len();
-
-                        for (template_name, config_entry) in config.iter() {
-                            let mustache_file = format!("{}.mustache", template_name);
-                            let template_path = repo_path.join(format!("templates/{}", mustache_file));
-                            let body_path = repo_path.join("templates/body.mustache");
-
-                            let actual_path = match (template_path.exists(), body_path.exists()) {
-                                (true, _) => template_path,
-                                (false, true) => body_path,
-                                _ => continue,
-                            };
-                            let path_str = actual_path.to_string_lossy().to_string();
-
-                            let (key, variant) = match is_tinted {
-                                true => match parse_tinted_template_name(template_name, tinted_short) {
-                                    Some((base_name, v)) => (sanitize_name(&base_name), v),
-                                    None => continue,
-                                },
-                                false => {
-                                    let key = match (template_count, template_name.as_str(), short_repo.as_str()) {
-                                        (1, _, _) | (_, "default", _) => short_repo.clone(),
-                                        (_, name, "css-etc" | "styles") => name.to_string(),
-                                        (_, name, _) => format!("{}-{}", short_repo, name),
-                                    };
-                                    let variant = match repo_name.starts_with("base24-") {
-                                        true => "base24",
-                                        false => "base16",
-                                    };
-                                    (key, variant)
-                                }
-                            };
-
-                            let extension = config_entry.extension();
-                            let entry = templates.entry(key.clone()).or_insert_with(|| TemplateInfo {
-                                name: key,
-                                base16_path: None,
-                                base24_path: None,
-                                extension: extension.clone(),
-                                _repo: repo_name.to_string(),
-                            });
-                            // Update extension if this entry has one and existing doesn't
-                            if entry.extension.is_empty() && !extension.is_empty() {
-                                entry.extension = extension;
-                            }
+                    let template_count = config.len();
+
+                    for (template_name, config_entry) in config.iter() {
+                        let mustache_file = format!("{}.mustache", template_name);
+                        let template_path = repo_path.join(format!("templates/{}", mustache_file));
+                        let body_path = repo_path.join("templates/body.mustache");
 
-                            match variant {
-                                "base16" => entry.base16_path = Some(path_str),
-                                "base24" => entry.base24_path = Some(path_str),
-                                "both" => {
-                                    entry.base16_path = Some(path_str.clone());
-                                    entry.base24_path = Some(path_str);
-                                }
-                                _ => {}
+                        let actual_path = match (template_path.exists(), body_path.exists()) {
+                            (true, _) => template_path,
+                            (false, true) => body_path,
+                            _ => continue,
+                        };
+
+                        let Some((key, variant)) = classify_template(
+                            repo_name, &short_repo, template_name, template_count, config_entry, &mut notes,
+                        ) else {
+                            continue;
+                        };
+
+                        let escape = config_entry.escape.as_deref().and_then(|mode| {
+                            let escape = render::Escape::parse(mode);
+                            if escape.is_none() {
+                                notes.push(lint::LintIssue {
+                                    path: format!("{}/templates/config.yaml", repo_name),
+                                    severity: lint::Severity::Warning,
+                                    kind: "template-escape",
+                                    message: format!("Unknown escape mode '{}' for {}, escaping by extension", mode, template_name),
+                                });
                             }
-                        }
+                            escape
+                        });
+                        let path = actual_path.to_string_lossy().to_string();
+                        files.entry(repo_name.to_string()).or_default().push(TemplateFile {
+                            name: template_name.clone(),
+                            variant,
+                            path: path.clone(),
+                            filename: config_entry.filename_pattern(),
+                            escape,
+                        });
+                        sources.push(TemplateSource {
+                            key,
+                            repo: repo_name.to_string(),
+                            variant,
+                            path,
+                            filename: config_entry.filename_pattern(),
+                            escape,
+                        });
                     }
-            }
+                }
+        }
+
+        let preference: Vec<String> = std::env::var("TEMPLATE_REPO_PREFERENCE")
+            .map(|list| list.split(',').map(|repo| repo.trim().to_string()).filter(|repo| !repo.is_empty()).collect())
+            .unwrap_or_default();
+        let (templates, qualified, mut lint) = Self::reconcile(sources, &preference);
+        for issue in notes.into_iter().chain(Self::check_files(&files)) {
+            lint.push(issue);
         }
 
         tracing::info!("Loaded {} templates into index", templates.len());
+        for issue in &lint.issues {
+            tracing::warn!("{} ({})", issue.message, issue.kind);
+        }
+
+        let aliases = Self::aliases_for(&templates);
+        let search = search::NameIndex::new(templates.keys().chain(aliases.keys()));
+        Ok(TemplateIndex { templates, qualified, aliases, search, lint, files })
+    }
+
+    /// Compile every template file, and compare the variables it interpolates with what
+    /// the builder spec provides for each system it's declared for.
+    fn check_files(files: &HashMap<String, Vec<TemplateFile>>) -> Vec<lint::LintIssue> {
+        let mut repos: Vec<_> = files.keys().collect();
+        repos.sort();
+        let mut issues = Vec::new();
+        let mut checked = std::collections::HashSet::new();
+        for file in repos.into_iter().flat_map(|repo| &files[repo]) {
+            let compiled = match compiled_template(&file.path) {
+                Ok(compiled) => compiled,
+                Err((_, message)) => {
+                    if checked.insert((file.path.as_str(), "")) {
+                        issues.push(lint::LintIssue {
+                            path: file.path.clone(),
+                            severity: lint::Severity::Error,
+                            kind: "template-compile-error",
+                            message,
+                        });
+                    }
+                    continue;
+                }
+            };
+            for system in ["base16", "base24"] {
+                if !file.provides(system) || !checked.insert((file.path.as_str(), system)) {
+                    continue;
+                }
+                let keys = spec_context_keys(system);
+                let missing = render::missing(&compiled.variables, |name| keys.contains(name));
+                if !missing.is_empty() {
+                    issues.push(lint::LintIssue {
+                        path: file.path.clone(),
+                        severity: lint::Severity::Warning,
+                        kind: "template-unresolved-variables",
+                        message: format!("{} renders {} with no value for {} schemes", file.path, missing.join(", "), system),
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Decide which repo owns each key. The owner is the first repo listed in
+    /// `preference`, then tinted-* repos, then by name. A slot it lacks is filled from
+    /// the single same-named repo providing it, which is how base16-x and base24-x pair
+    /// up. Two repos filling the same slot, or unrelated repos sharing a key, is a
+    /// collision: the owner keeps the short key and every repo stays reachable as
+    /// `{repo}:{key}`.
+    fn reconcile(
+        sources: Vec<TemplateSource>,
+        preference: &[String],
+    ) -> (HashMap<String, TemplateInfo>, HashMap<String, TemplateInfo>, lint::LintReport) {
+        let mut by_key: BTreeMap<String, Vec<TemplateSource>> = BTreeMap::new();
+        for source in sources {
+            by_key.entry(source.key.clone()).or_default().push(source);
+        }
+
+        let rank = |repo: &str| (
+            preference.iter().position(|p| p == repo).unwrap_or(usize::MAX),
+            !repo.starts_with("tinted-"),
+            repo.to_string(),
+        );
+        let family = |repo: &str| -> String {
+            let short = ["tinted-", "base16-", "base24-"].iter()
+                .find_map(|prefix| repo.strip_prefix(prefix))
+                .unwrap_or(repo);
+            short.to_string()
+        };
 
-        Ok(TemplateIndex { templates })
+        let mut templates = HashMap::new();
+        let mut qualified = HashMap::new();
+        let mut lint = lint::LintReport::default();
+        for (key, sources) in by_key {
+            let mut repos: Vec<&str> = sources.iter().map(|s| s.repo.as_str()).collect();
+            repos.sort();
+            repos.dedup();
+            for repo in &repos {
+                let name = format!("{}:{}", repo, key).to_lowercase();
+                let info = TemplateInfo::from_sources(name.clone(), sources.iter().filter(|s| s.repo == *repo));
+                qualified.insert(name, info);
+            }
+
+            let owner = *repos.iter().min_by_key(|repo| rank(repo)).unwrap();
+            let providers = |system: &str| -> Vec<&TemplateSource> {
+                let mut found: Vec<&TemplateSource> = sources.iter().filter(|s| s.provides(system)).collect();
+                found.dedup_by(|a, b| a.repo == b.repo);
+                found
+            };
+            let contested = ["base16", "base24"].iter().any(|system| providers(system).len() > 1)
+                || repos.iter().any(|repo| family(repo) != family(owner));
+
+            let mut info = TemplateInfo::from_sources(key.clone(), sources.iter().filter(|s| s.repo == owner));
+            for system in ["base16", "base24"] {
+                if info.path_for_slot(system).is_some() {
+                    continue;
+                }
+                if let [source] = providers(system).as_slice()
+                    && family(&source.repo) == family(owner) {
+                    info.fill(system, source);
+                }
+            }
+
+            if contested {
+                let others: Vec<String> = repos.iter().filter(|r| **r != owner).map(|r| format!("{}:{}", r, key)).collect();
+                lint.push(lint::LintIssue {
+                    path: repos.join(", "),
+                    severity: lint::Severity::Warning,
+                    kind: "template-collision",
+                    message: format!("Template '{}' is provided by {}; {} owns it, the others are {}",
+                        key, repos.join(" and "), owner, others.join(", ")),
+                });
+            }
+            templates.insert(key, info);
+        }
+        lint.files = templates.len();
+
+        (templates, qualified, lint)
     }
 
+    fn aliases_for(templates: &HashMap<String, TemplateInfo>) -> HashMap<String, String> {
+        let derived = templates.keys()
+            .flat_map(|key| ["base16-", "base24-", "tinted-"].map(|prefix| (format!("{}{}", prefix, key), key.clone())));
+        let declared = TEMPLATE_ALIASES.iter()
+            .flat_map(|(key, names)| names.iter().map(|name| (name.to_string(), key.to_string())));
+        // A real template always wins over an alias of the same name
+        derived.chain(declared)
+            .filter(|(alias, key)| templates.contains_key(key) && !templates.contains_key(alias))
+            .collect()
+    }
+
+    /// Template by key, alias or `{repo}:{key}`, ignoring case.
     fn find(&self, name: &str) -> Option<&TemplateInfo> {
-        self.templates.get(&name.to_lowercase())
+        let name = name.to_lowercase();
+        if name.contains(':') {
+            return self.qualified.get(&name);
+        }
+        self.templates.get(&name)
+            .or_else(|| self.aliases.get(&name).and_then(|key| self.templates.get(key)))
+    }
+
+    /// Every file the repo behind `info`'s template for a `system` scheme defines for the
+    /// same system, the template itself included.
+    fn files_for(&self, info: &TemplateInfo, system: &str) -> Vec<&TemplateFile> {
+        let Some(path) = info.path_for_system(system) else {
+            return Vec::new();
+        };
+        // A base24 scheme rendered with base16 templates gets the base16 set
+        let slot = match info.path_for_slot(system) {
+            Some(_) => system,
+            None => "base16",
+        };
+        let Some(repo_files) = self.files.values().find(|files| files.iter().any(|f| f.path == path)) else {
+            return Vec::new();
+        };
+        repo_files.iter().filter(|f| f.provides(slot)).collect()
+    }
+
+    /// Templates whose key or an alias is similar to `name`, best first.
+    fn similar(&self, name: &str, threshold: f64) -> Vec<(&TemplateInfo, f64)> {
+        let mut seen = std::collections::HashSet::new();
+        self.search.similar(name)
+            .into_iter()
+            .filter(|(_, score)| *score >= threshold)
+            .filter_map(|(matched, score)| Some((self.find(matched)?, score)))
+            .filter(|(info, _)| seen.insert(info.name.as_str()))
+            .collect()
     }
 
-    fn sorted_names(&self) -> Vec<String> {
-        let mut names: Vec<String> = self.templates.keys().cloned().collect();
+    /// Templates available for at least one system the host serves.
+    fn sorted_names_for(&self, profile: &hosts::HostProfile) -> Vec<String> {
+        let mut names: Vec<String> = self.templates.values()
+            .filter(|t| profile.systems.iter().any(|system| t.path_for_system(system).is_some()))
+            .map(|t| t.name.clone())
+            .collect();
         names.sort();
         names
     }
@@ -427,6 +1098,7 @@ impl TemplateIndex {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "RawSchemeYaml")]
 struct SchemeYaml {
     #[serde(default, skip_serializing_if = "String::is_empty")]
     system: String,
@@ -436,52 +1108,236 @@ struct SchemeYaml {
     slug: Option<String>,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     variant: String,
-    palette: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    palette: Palette,
+    /// Keys outside the spec, kept so JSON/YAML conversion round-trips them.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_yaml::Value>,
 }
 
+/// Scheme as written on disk: either the tinted format with a `palette:` map, or the
+/// legacy format with top-level `scheme:` and flat `base00: "282a36"` keys.
 #[derive(Deserialize)]
-struct FormatQuery {
+struct RawSchemeYaml {
     #[serde(default)]
-    format: Option<String>,
+    system: Option<String>,
     #[serde(default)]
-    order: Option<String>,
-}
-
-#[derive(Deserialize)]
-struct IndexQuery {
+    name: Option<String>,
     #[serde(default)]
-    sort: Option<String>,
+    scheme: Option<String>,
     #[serde(default)]
-    view: Option<String>,
+    author: Option<String>,
     #[serde(default)]
-    format: Option<String>,
+    slug: Option<String>,
     #[serde(default)]
-    filter: Option<String>,
+    variant: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    palette: Option<HashMap<String, serde_yaml::Value>>,
+    #[serde(flatten)]
+    rest: HashMap<String, serde_yaml::Value>,
 }
 
+impl TryFrom<RawSchemeYaml> for SchemeYaml {
+    type Error = String;
+
+    fn try_from(raw: RawSchemeYaml) -> Result<Self, Self::Error> {
+        let author = raw.author.ok_or("missing field `author`")?;
+
+        if let Some(palette) = raw.palette {
+            let name = raw.name.ok_or("missing field `name`")?;
+            return Ok(SchemeYaml {
+                system: raw.system.unwrap_or_default(),
+                name,
+                author,
+                slug: raw.slug,
+                variant: raw.variant.unwrap_or_default(),
+                description: raw.description,
+                palette: Palette::from_strings(yaml_hex_strings(palette)),
+                extra: raw.rest.into_iter().collect(),
+            });
+        }
+
+        let name = raw.scheme.or(raw.name).ok_or("missing field `palette` (or legacy `scheme`)")?;
+        let (flat, extra): (HashMap<_, _>, HashMap<_, _>) = raw.rest.into_iter()
+            .partition(|(key, _)| key.parse::<Slot>().is_ok());
+        let palette = Palette::from_strings(yaml_hex_strings(flat));
+        if palette.is_empty() {
+            return Err("missing field `palette` (or legacy `base00`..`base0F`)".to_string());
+        }
 
-#[derive(Serialize)]
-struct HelpResponse {
-    schemes: Vec<String>,
-    templates: Vec<String>,
+        let system = raw.system.unwrap_or_else(|| match palette.contains(Slot::Base10) {
+            true => "base24".to_string(),
+            false => "base16".to_string(),
+        });
+        Ok(SchemeYaml {
+            system,
+            name,
+            author,
+            slug: raw.slug,
+            variant: raw.variant.unwrap_or_default(),
+            description: raw.description,
+            palette,
+            extra: extra.into_iter().collect(),
+        })
+    }
 }
 
-fn sanitize_name(name: &str) -> String {
-    name.chars()
-        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
-        .take(255)
+/// Palette values as strings. Unquoted all-digit hex like 282828 parses as a number,
+/// so it is zero-padded back; values that can't be read as hex are left out, and the
+/// linter reports them.
+fn yaml_hex_strings(values: HashMap<String, serde_yaml::Value>) -> HashMap<String, String> {
+    values.into_iter()
+        .filter_map(|(key, value)| {
+            let hex = yaml_hex_string(&key, &value).ok()?;
+            Some((key, hex))
+        })
         .collect()
 }
 
-fn get_base_description(base: &str) -> Option<&'static str> {
-    match base {
-        "base00" => Some("Default Background"),
-        "base01" => Some("Lighter Background (status bars, line numbers)"),
-        "base02" => Some("Selection Background"),
-        "base03" => Some("Comments, Invisibles, Line Highlighting"),
-        "base04" => Some("Dark Foreground (status bars)"),
-        "base05" => Some("Default Foreground, Caret, Delimiters, Operators"),
-        "base06" => Some("Light Foreground"),
+/// One palette value as written. Unquoted hex like `123e45` reads as a float, and the
+/// digits can't be recovered from it (`120e45` and `012e46` are the same number).
+fn yaml_hex_string(key: &str, value: &serde_yaml::Value) -> Result<String, String> {
+    match value {
+        serde_yaml::Value::String(s) => Ok(s.clone()),
+        serde_yaml::Value::Number(n) if n.is_u64() => Ok(format!("{:06}", n.as_u64().unwrap())),
+        serde_yaml::Value::Number(n) if n.is_f64() => {
+            Err(format!("{} was read by YAML as the number {}; quote the hex colour", key, n))
+        }
+        _ => Err(format!("{} must be a hex colour", key)),
+    }
+}
+
+impl SchemeYaml {
+    /// Whether a scheme file uses the legacy flat layout rather than a `palette:` map.
+    fn is_legacy(yaml: &str) -> bool {
+        serde_yaml::from_str::<serde_yaml::Value>(yaml)
+            .map(|v| Self::is_legacy_document(&v))
+            .unwrap_or(false)
+    }
+
+    /// The rule `TryFrom<RawSchemeYaml>` applies: no `palette:` map, so the slots are
+    /// top-level keys and the name is under `scheme:` or `name:`.
+    fn is_legacy_document(value: &serde_yaml::Value) -> bool {
+        value.is_mapping() && value.get("palette").is_none()
+    }
+
+    /// Serialize in the legacy format understood by older base16 builders.
+    fn to_legacy_yaml(&self) -> String {
+        let quote = |s: &str| serde_json::to_string(s).unwrap();
+        let mut yaml = format!("scheme: {}\nauthor: {}\n", quote(&self.name), quote(&self.author));
+        for (slot, color) in self.palette.iter() {
+            yaml.push_str(&format!("{}: {}\n", slot, quote(&color.hex_digits())));
+        }
+        yaml
+    }
+}
+
+#[derive(Deserialize)]
+struct TemplateQuery {
+    /// `tinted` renders with exactly the tinted-theming builder spec variables.
+    #[serde(default)]
+    compat: Option<String>,
+    /// `0` serves the output inline instead of as an attachment.
+    #[serde(default)]
+    download: Option<String>,
+    /// `html` shows the output highlighted in the scheme's colours.
+    #[serde(default)]
+    format: Option<String>,
+    /// `1` answers with the template's variables and those the scheme can't supply.
+    #[serde(default)]
+    debug: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    order: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FromImageQuery {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    variant: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ValidateQuery {
+    #[serde(default)]
+    system: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct IndexQuery {
+    #[serde(default)]
+    sort: Option<String>,
+    #[serde(default)]
+    view: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    filter: Option<String>,
+}
+
+
+#[derive(Serialize)]
+struct HelpResponse {
+    schemes: Vec<String>,
+    templates: Vec<String>,
+}
+
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(255)
+        .collect()
+}
+
+/// Like `sanitize_name`, but keeps the `:` in `{repo}:{template}`.
+fn sanitize_template_name(name: &str) -> String {
+    match name.split_once(':') {
+        Some((repo, key)) => format!("{}:{}", sanitize_name(repo), sanitize_name(key)),
+        None => sanitize_name(name),
+    }
+}
+
+fn get_base_description(base: &str) -> Option<&'static str> {
+    match base {
+        "base00" => Some("Default Background"),
+        "base01" => Some("Lighter Background (status bars, line numbers)"),
+        "base02" => Some("Selection Background"),
+        "base03" => Some("Comments, Invisibles, Line Highlighting"),
+        "base04" => Some("Dark Foreground (status bars)"),
+        "base05" => Some("Default Foreground, Caret, Delimiters, Operators"),
+        "base06" => Some("Light Foreground"),
         "base07" => Some("Lightest Foreground"),
         "base08" => Some("Variables, XML Tags, Markup Link Text, Diff Deleted"),
         "base09" => Some("Integers, Booleans, Constants, XML Attributes"),
@@ -528,11 +1384,10 @@ fn colorize_yaml_hex_values(yaml: &str, fg_hex: &str) -> String {
             });
 
             // Wrap with tooltip if it's a baseXX line
-            if let Some(base) = &base_name {
-                if let Some(desc) = get_base_description(base) {
+            if let Some(base) = &base_name
+                && let Some(desc) = get_base_description(base) {
                     return format!(r#"<span class="palette-row" title="{}">{}</span>"#, desc, colored_line);
                 }
-            }
             colored_line.to_string()
         })
         .collect::<Vec<_>>()
@@ -546,17 +1401,13 @@ fn build_palette_svg(scheme_data: &SchemeYaml, width: u32, height: u32, rect_wid
         let mut svg = format!(r#"<svg viewBox="0 0 {} {}" preserveAspectRatio="none" shape-rendering="crispEdges" xmlns="http://www.w3.org/2000/svg">"#, width, total_height);
         // First row: base00-base0F
         for i in 0..16 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[i as usize]);
             svg.push_str(&format!(r#"<rect x="{}" y="0" width="{}" height="{}" fill="{}"/>"#, i * rect_width, rect_width, height, color));
         }
         // Second row: base10-base17 (8 colors, double width)
         let wide_rect = rect_width * 2;
         for i in 0..8 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", 0x10 + i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[0x10 + i as usize]);
             svg.push_str(&format!(r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#, i * wide_rect, height, wide_rect, height, color));
         }
         svg.push_str("</svg>");
@@ -564,9 +1415,7 @@ fn build_palette_svg(scheme_data: &SchemeYaml, width: u32, height: u32, rect_wid
     } else {
         let mut svg = format!(r#"<svg viewBox="0 0 {} {}" preserveAspectRatio="none" shape-rendering="crispEdges" xmlns="http://www.w3.org/2000/svg">"#, width, height);
         for i in 0..16 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[i as usize]);
             svg.push_str(&format!(r#"<rect x="{}" y="0" width="{}" height="{}" fill="{}"/>"#, i * rect_width, rect_width, height, color));
         }
         svg.push_str("</svg>");
@@ -580,18 +1429,14 @@ fn build_palette_grid_svg(scheme_data: &SchemeYaml, is_base24: bool) -> String {
         let mut svg = String::from(r#"<svg viewBox="0 0 4 6" preserveAspectRatio="none" shape-rendering="crispEdges" xmlns="http://www.w3.org/2000/svg">"#);
         // Base16 colors (4x4)
         for i in 0..16 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[i]);
             let x = i % 4;
             let y = i / 4;
             svg.push_str(&format!(r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#, x, y, color));
         }
         // Base24 extra colors (4x2)
         for i in 0..8 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", 0x10 + i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[0x10 + i]);
             let x = i % 4;
             let y = 4 + i / 4;
             svg.push_str(&format!(r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#, x, y, color));
@@ -601,9 +1446,7 @@ fn build_palette_grid_svg(scheme_data: &SchemeYaml, is_base24: bool) -> String {
     } else {
         let mut svg = String::from(r#"<svg viewBox="0 0 4 4" preserveAspectRatio="none" shape-rendering="crispEdges" xmlns="http://www.w3.org/2000/svg">"#);
         for i in 0..16 {
-            let color = scheme_data.palette.get(&format!("base{:02X}", i))
-                .cloned()
-                .unwrap_or_else(|| "#000000".to_string());
+            let color = scheme_data.palette.hex_or_black(Slot::ALL[i]);
             let x = i % 4;
             let y = i / 4;
             svg.push_str(&format!(r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#, x, y, color));
@@ -618,28 +1461,201 @@ struct SchemePath {
     scheme: String,
 }
 
+#[derive(Deserialize)]
+struct BundleQuery {
+    /// Comma-separated template names, or `all`.
+    templates: Option<String>,
+    /// `zip`, or `tar.gz` (the default).
+    format: Option<String>,
+    compat: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BundleManifest<'a> {
+    scheme: &'a str,
+    system: &'a str,
+    templates: Vec<BundledTemplate>,
+    skipped: Vec<SkippedTemplate>,
+}
+
+#[derive(Serialize)]
+struct BundledTemplate {
+    name: String,
+    path: String,
+}
+
+#[derive(Serialize)]
+struct SkippedTemplate {
+    name: String,
+    reason: String,
+}
+
+/// One (scheme, template) pair in a `POST /--batch` body.
+#[derive(Deserialize)]
+struct BatchItem {
+    /// Scheme name, optionally pinned as `base16/{scheme}` or `base24/{scheme}`.
+    scheme: String,
+    template: String,
+    /// Key for this item in the response; defaults to `{scheme}/{template}`.
+    #[serde(default)]
+    id: Option<String>,
+    /// Slot colours to replace before rendering, as in `/--edit` permalinks.
+    #[serde(default)]
+    overrides: BTreeMap<String, String>,
+    #[serde(default)]
+    compat: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+struct BatchResponse {
+    outputs: BTreeMap<String, String>,
+    errors: BTreeMap<String, String>,
+}
+
+/// One scheme's file in an `/--all/{template}.tar.gz` archive.
+#[derive(Serialize)]
+struct ArchivedScheme {
+    slug: String,
+    system: String,
+    file: String,
+}
+
 #[derive(Deserialize)]
 struct SchemeTemplatePath {
     scheme: String,
     template: String,
 }
 
+/// Look up a scheme the request's host serves: in the pinned system if any, otherwise
+/// preferring the host's default system.
+fn find_served(name: &str, pin: Option<SystemPin>, profile: &hosts::HostProfile) -> Option<&'static SchemeInfo> {
+    let system = pin.map(|SystemPin(system)| system).or(profile.single_system());
+    SCHEME_INDEX.find(name, system, &profile.default_system)
+        .filter(|info| profile.serves(&info.system))
+}
+
+/// Resolve a `{scheme}` path segment, honouring a `/base16/` or `/base24/` prefix and
+/// the host profile. Non-canonical, renamed and misspelled names redirect, keeping the
+/// prefix and appending `suffix` (e.g. "/--edit"); removed ones are Gone.
+fn resolve_scheme(scheme: &str, pin: Option<SystemPin>, headers: &HeaderMap, suffix: &str) -> Result<&'static SchemeInfo, Miss> {
+    let sanitized = sanitize_name(scheme);
+    let profile = HOST_PROFILES.for_headers(headers);
+    let prefix = pin.map(|SystemPin(s)| format!("/{}", s)).unwrap_or_default();
+
+    if let Some(info) = find_served(&sanitized, pin, profile) {
+        if scheme != info.name {
+            return Err(Miss::Redirect(format!("{}/{}{}", prefix, info.name, suffix)));
+        }
+        Ok(info)
+    } else if let Some(miss) = alias_served(&sanitized, pin, profile, &prefix, suffix) {
+        Err(miss)
+    } else {
+        let candidates = fuzzy_served(&sanitized, pin, profile, SUGGEST_THRESHOLD);
+        let location = |info: &SchemeInfo| format!("{}/{}{}", prefix, info.name, suffix);
+        let choices = |candidates: &[(&SchemeInfo, f64)]| candidates.iter()
+            .map(|(info, score)| Choice {
+                name: info.name.clone(),
+                system: Some(info.system.clone()),
+                url: location(info),
+                score: round_score(*score),
+            })
+            .collect();
+
+        let scores: Vec<f64> = candidates.iter().map(|(_, score)| *score).collect();
+        match likely_matches(&scores) {
+            0 => Err(Miss::NotFound(Suggestions::new("Scheme", &sanitized, choices(&candidates), headers))),
+            1 => Err(Miss::Guess(location(candidates[0].0))),
+            n => Err(Miss::Ambiguous(Suggestions::new("Scheme", &sanitized, choices(&candidates[..n]), headers))),
+        }
+    }
+}
+
+/// Similarity scores are reported to three decimals.
+fn round_score(score: f64) -> f64 {
+    (score * 1000.0).round() / 1000.0
+}
+
+/// How many of the best-first `scores` are plausible targets: 0 when nothing is close
+/// enough, 1 when the best clearly wins, more when runners-up are about as good.
+fn likely_matches(scores: &[f64]) -> usize {
+    let likely = scores.iter().take_while(|score| **score >= FUZZY_THRESHOLD).count();
+    match scores {
+        [best, second, ..] if likely > 1 && best - second < FUZZY_MARGIN => likely,
+        _ => likely.min(1),
+    }
+}
+
+/// Renamed and removed slugs, checked before fuzzy matching so old links never land on
+/// an unrelated scheme.
+fn alias_served(name: &str, pin: Option<SystemPin>, profile: &hosts::HostProfile, prefix: &str, suffix: &str) -> Option<Miss> {
+    let location = |info: &SchemeInfo| format!("{}/{}{}", prefix, info.name, suffix);
+    match SCHEME_INDEX.aliases.lookup(name)? {
+        aliases::Alias::Renamed(to) => find_served(to, pin, profile).map(|info| Miss::Renamed(location(info))),
+        aliases::Alias::Removed(successor) => Some(Miss::Gone {
+            name: name.to_string(),
+            successor: successor.and_then(|s| find_served(s, pin, profile)).map(location),
+        }),
+    }
+}
+
+/// Resolve a `{template}` path segment. Aliases and other spellings redirect to
+/// `{base}/{key}`; typos go through the same guess/choices logic as schemes, offering
+/// only templates for which `available` holds.
+fn resolve_template(
+    template: &str,
+    base: &str,
+    suffix: &str,
+    headers: &HeaderMap,
+    available: impl Fn(&TemplateInfo) -> bool,
+) -> Result<&'static TemplateInfo, Miss> {
+    let sanitized = sanitize_template_name(template);
+    if let Some(info) = TEMPLATE_INDEX.find(&sanitized) {
+        return match template == info.name {
+            true => Ok(info),
+            false => Err(Miss::Redirect(format!("{}/{}{}", base, info.name, suffix))),
+        };
+    }
+
+    let candidates: Vec<(&TemplateInfo, f64)> = TEMPLATE_INDEX.similar(&sanitized, SUGGEST_THRESHOLD)
+        .into_iter()
+        .filter(|(info, _)| available(info))
+        .take(MAX_SUGGESTIONS)
+        .collect();
+    let choices = |candidates: &[(&TemplateInfo, f64)]| candidates.iter()
+        .map(|(info, score)| Choice {
+            name: info.name.clone(),
+            system: None,
+            url: format!("{}/{}{}", base, info.name, suffix),
+            score: round_score(*score),
+        })
+        .collect();
+    let scores: Vec<f64> = candidates.iter().map(|(_, score)| *score).collect();
+    match likely_matches(&scores) {
+        0 => Err(Miss::NotFound(Suggestions::new("Template", &sanitized, choices(&candidates), headers))),
+        1 => Err(Miss::Guess(format!("{}/{}{}", base, candidates[0].0.name, suffix))),
+        n => Err(Miss::Ambiguous(Suggestions::new("Template", &sanitized, choices(&candidates[..n]), headers))),
+    }
+}
+
+/// Served schemes similar to `name`, best first, at most `MAX_SUGGESTIONS`.
+fn fuzzy_served(name: &str, pin: Option<SystemPin>, profile: &hosts::HostProfile, threshold: f64) -> Vec<(&'static SchemeInfo, f64)> {
+    let system = pin.map(|SystemPin(system)| system).or(profile.single_system());
+    SCHEME_INDEX.fuzzy_slugs(name, threshold, system)
+        .into_iter()
+        .filter_map(|(slug, score)| Some((find_served(slug, pin, profile)?, score)))
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
 async fn handle_scheme(
     Path(SchemePath { scheme }): Path<SchemePath>,
+    pin: Option<Extension<SystemPin>>,
     Query(query): Query<FormatQuery>,
     headers: HeaderMap,
 ) -> Response {
-    let sanitized = sanitize_name(&scheme);
-
-    let scheme_info = if let Some(info) = SCHEME_INDEX.find_exact(&sanitized) {
-        if scheme != info.name {
-            return Redirect::permanent(&format!("/{}", info.name)).into_response();
-        }
-        info
-    } else if let Some(info) = SCHEME_INDEX.find_fuzzy(&sanitized, 0.8) {
-        return Redirect::permanent(&format!("/{}", info.name)).into_response();
-    } else {
-        return (StatusCode::NOT_FOUND, format!("Scheme '{}' not found", sanitized)).into_response();
+    let scheme_info = match resolve_scheme(&scheme, pin.map(|Extension(p)| p), &headers, "") {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
     };
 
     let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
@@ -658,7 +1674,20 @@ async fn handle_scheme(
         .map(|v| v.contains("text/html"))
         .unwrap_or(false);
 
-    if wants_json {
+    if query.format.as_deref() == Some("legacy-yaml") {
+        let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+            Ok(d) => d,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+        };
+
+        Response::builder()
+            .header("content-type", "application/yaml")
+            .header("vary", "Accept")
+            .header("x-scheme-name", &scheme_info.name)
+            .header("x-scheme-system", &scheme_info.system)
+            .body(Body::from(scheme_data.to_legacy_yaml()))
+            .unwrap()
+    } else if wants_json {
         let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
             Ok(d) => d,
             Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
@@ -682,16 +1711,23 @@ async fn handle_scheme(
             Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
         };
 
-        let fg = scheme_data.palette.get("base05").cloned().unwrap_or_else(|| "#ffffff".to_string()).trim_start_matches('#').to_string();
+        let fg = scheme_data.palette.get(Slot::Base05).unwrap_or(Rgb::new(255, 255, 255)).hex_digits();
+        // Show legacy files in the normalized layout so the hex values are highlighted
+        let yaml_source = match SchemeYaml::is_legacy(&scheme_yaml_str) {
+            true => serde_yaml::to_string(&scheme_data).unwrap_or_default(),
+            false => scheme_yaml_str.clone(),
+        };
         let is_base24 = scheme_info.system == "base24";
         let palette_svg = build_palette_svg(&scheme_data, 320, 40, 20, is_base24);
 
         // Determine sort order and compute prev/next
         let by_color = query.order.as_deref() == Some("color");
         let order_param = if by_color { "?order=color" } else { "" };
-        let (prev, next) = SCHEME_INDEX.get_neighbors(&scheme_info.name, by_color);
+        let profile = HOST_PROFILES.for_headers(&headers);
+        let (prev, next) = SCHEME_INDEX.get_neighbors(&scheme_info.id, by_color, profile);
 
-        let schemes_json = serde_json::to_string(&SCHEME_INDEX.names_sorted).unwrap();
+        let served: Vec<&str> = SCHEME_INDEX.served(profile).map(|info| info.path_on(profile)).collect();
+        let schemes_json = serde_json::to_string(&served).unwrap();
 
         // Get templates compatible with this scheme's system
         let templates = TEMPLATE_INDEX.templates_for_system(&scheme_info.system);
@@ -700,22 +1736,29 @@ async fn handle_scheme(
         let mut data = MapBuilder::new()
             .insert_str("scheme-name", &scheme_data.name)
             .insert_str("scheme-slug", slug)
+            .insert_str("scheme-url", scheme_info.path_on(profile))
+            .insert_str("origin", &profile.origin)
+            .insert_str("site-name", &profile.host)
             .insert_str("scheme-author", &scheme_data.author)
             .insert_str("scheme-system", &scheme_info.system)
+            .insert_str("edit-href", format!("/{}/--edit", scheme_info.path_on(profile)))
             .insert_str("palette-svg", &palette_svg)
-            .insert_str("yaml-colorized", colorize_yaml_hex_values(&scheme_yaml_str, &fg))
+            .insert_str("yaml-colorized", colorize_yaml_hex_values(&yaml_source, &fg))
             .insert_str("schemes-json", &schemes_json)
             .insert_vec("templates", |mut vec| {
                 for template in &templates {
                     vec = vec.push_map(|map| {
                         map.insert_str("name", &template.name)
-                           .insert_str("filename", template.download_filename(slug))
-                           .insert_str("url", format!("/{}/{}", scheme_info.name, template.name))
+                           .insert_str("filename", template.download_filename(slug, &scheme_info.system))
+                           .insert_str("url", format!("/{}/{}", scheme_info.path_on(profile), template.name))
                     });
                 }
                 vec
             });
 
+        if let Some(description) = &scheme_data.description {
+            data = data.insert_str("scheme-description", description);
+        }
         if let Some(prev_name) = prev {
             data = data.insert_str("prev-scheme", prev_name)
                        .insert_str("prev-href", format!("/{}{}", prev_name, order_param))
@@ -727,9 +1770,8 @@ async fn handle_scheme(
                        .insert_bool("has-next", true);
         }
 
-        for (key, value) in &scheme_data.palette {
-            let hex_value = value.trim_start_matches('#');
-            data = data.insert_str(format!("{}-hex", key), hex_value);
+        for (slot, color) in scheme_data.palette.iter() {
+            data = data.insert_str(format!("{}-hex", slot), color.hex_digits());
         }
 
         let html = match SCHEME_TEMPLATE.render_data_to_string(&data.build()) {
@@ -744,6 +1786,20 @@ async fn handle_scheme(
             .header("x-scheme-system", &scheme_info.system)
             .body(Body::from(html))
             .unwrap()
+    } else if SchemeYaml::is_legacy(&scheme_yaml_str) {
+        // Legacy files are served in the current format; ?format=legacy-yaml gets the old one
+        let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+            Ok(d) => d,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+        };
+
+        Response::builder()
+            .header("content-type", "application/yaml")
+            .header("vary", "Accept")
+            .header("x-scheme-name", &scheme_info.name)
+            .header("x-scheme-system", &scheme_info.system)
+            .body(Body::from(serde_yaml::to_string(&scheme_data).unwrap()))
+            .unwrap()
     } else {
         match File::open(&scheme_info.path).await {
             Ok(file) => {
@@ -776,10 +1832,12 @@ async fn handle_index(Query(query): Query<IndexQuery>, headers: HeaderMap) -> Re
         _ => "json",
     };
 
+    let profile = HOST_PROFILES.for_headers(&headers);
+
     if format != "html" {
         let response = HelpResponse {
-            schemes: SCHEME_INDEX.names_sorted.clone(),
-            templates: TEMPLATE_INDEX.sorted_names(),
+            schemes: SCHEME_INDEX.served(profile).map(|info| info.path_on(profile).to_string()).collect(),
+            templates: TEMPLATE_INDEX.sorted_names_for(profile),
         };
 
         return match format {
@@ -808,29 +1866,33 @@ async fn handle_index(Query(query): Query<IndexQuery>, headers: HeaderMap) -> Re
     let filter_base16 = filter == "base16";
     let filter_base24 = filter == "base24";
 
-    let mut schemes_with_data: Vec<(String, SchemeYaml, String, String)> = SCHEME_INDEX
-        .schemes
-        .iter()
-        .filter_map(|(name, info)| {
+    // Always sort alphabetically - color order is handled via CSS
+    let schemes_with_data: Vec<(&SchemeInfo, SchemeYaml)> = SCHEME_INDEX.served(profile)
+        .filter_map(|info| {
             let yaml_str = std::fs::read_to_string(&info.path).ok()?;
             let scheme_data: SchemeYaml = serde_yaml::from_str(&yaml_str).ok()?;
-            Some((name.clone(), scheme_data, info.path.clone(), info.system.clone()))
+            Some((info, scheme_data))
         })
         .collect();
 
-    // Always sort alphabetically - color order is handled via CSS
-    schemes_with_data.sort_by(|(name_a, _, _, _), (name_b, _, _, _)| name_a.cmp(name_b));
-
-    let template_names = TEMPLATE_INDEX.sorted_names();
+    let template_names = TEMPLATE_INDEX.sorted_names_for(profile);
+    let example_scheme = find_served("monokai", None, profile)
+        .or(schemes_with_data.first().map(|(info, _)| *info))
+        .map(|info| info.path_on(profile))
+        .unwrap_or("monokai");
 
-    let base16_count = schemes_with_data.iter().filter(|(_, _, _, sys)| sys == "base16").count();
-    let base24_count = schemes_with_data.iter().filter(|(_, _, _, sys)| sys == "base24").count();
+    let base16_count = schemes_with_data.iter().filter(|(info, _)| info.system == "base16").count();
+    let base24_count = schemes_with_data.iter().filter(|(info, _)| info.system == "base24").count();
 
     let data = MapBuilder::new()
         .insert_str("scheme-count", schemes_with_data.len().to_string())
         .insert_str("base16-count", base16_count.to_string())
         .insert_str("base24-count", base24_count.to_string())
         .insert_str("template-count", template_names.len().to_string())
+        .insert_str("origin", &profile.origin)
+        .insert_str("site-name", &profile.host)
+        .insert_str("example-scheme", example_scheme)
+        .insert_bool("multi-system", profile.single_system().is_none())
         .insert_bool("sort-by-name", !sort_by_color)
         .insert_bool("sort-by-color", sort_by_color)
         .insert_bool("view-grid", view_grid)
@@ -844,19 +1906,21 @@ async fn handle_index(Query(query): Query<IndexQuery>, headers: HeaderMap) -> Re
                 .map(|(i, name)| (name.as_str(), i))
                 .collect();
 
-            for (name, scheme_data, _, system) in &schemes_with_data {
-                let is_base24 = system == "base24";
+            for (info, scheme_data) in &schemes_with_data {
+                let is_base24 = info.system == "base24";
                 let palette_svg = build_palette_svg(scheme_data, 224, 20, 14, is_base24);
                 let palette_grid_svg = build_palette_grid_svg(scheme_data, is_base24);
-                let color_pos = color_order_map.get(name.as_str()).copied().unwrap_or(0);
+                let color_pos = color_order_map.get(info.id.as_str()).copied().unwrap_or(0);
                 vec = vec.push_map(|map| {
-                    map.insert_str("name", name.as_str())
+                    map.insert_str("name", info.name.as_str())
+                       .insert_str("url", info.path_on(profile))
+                       .insert_bool("qualified", info.path_on(profile) != info.name)
                        .insert_str("palette-svg", &palette_svg)
                        .insert_str("palette-grid-svg", &palette_grid_svg)
                        .insert_str("color-order", color_pos.to_string())
-                       .insert_str("system", system.as_str())
-                       .insert_bool("is-base16", system == "base16")
-                       .insert_bool("is-base24", system == "base24")
+                       .insert_str("system", info.system.as_str())
+                       .insert_bool("is-base16", info.system == "base16")
+                       .insert_bool("is-base24", info.system == "base24")
                 });
             }
             vec
@@ -887,9 +1951,10 @@ async fn handle_help(
     Query(query): Query<FormatQuery>,
     headers: HeaderMap,
 ) -> Response {
+    let profile = HOST_PROFILES.for_headers(&headers);
     let help = HelpResponse {
-        schemes: SCHEME_INDEX.names_sorted.clone(),
-        templates: TEMPLATE_INDEX.sorted_names(),
+        schemes: SCHEME_INDEX.served(profile).map(|info| info.path_on(profile).to_string()).collect(),
+        templates: TEMPLATE_INDEX.sorted_names_for(profile),
     };
 
     let wants_json = query.format.as_deref() == Some("json")
@@ -906,21 +1971,45 @@ async fn handle_help(
             .body(Body::from(json))
             .unwrap()
     } else {
-        let mut text = String::from("base16.sh - Base16/Base24 Theme Server\n\n");
+        let mut text = format!("{} - Base16/Base24 Theme Server\n\n", profile.host);
 
         text.push_str("Endpoints:\n");
         text.push_str("  GET /                      - list schemes and templates (HTML/JSON/YAML)\n");
         text.push_str("  GET /{scheme}              - scheme colors (YAML/JSON/HTML)\n");
-        text.push_str("  GET /{scheme}/{template}   - render scheme through template\n");
+        text.push_str("  GET /{scheme}/{template}   - render scheme through template (?compat=tinted for\n");
+        text.push_str("                               exactly the tinted builder spec variables, ?download=0\n");
+        text.push_str("                               to view inline, ?format=html for a highlighted preview,\n");
+        text.push_str("                               ?debug=1 for the variables the scheme can't supply)\n");
+        text.push_str("  GET /{scheme}/{template}.zip - every file the template's repo defines, as an archive\n");
+        text.push_str("                               (also .tar.gz)\n");
+        text.push_str("  GET /{scheme}/--edit       - interactive palette editor (?base08=ff0000 permalinks)\n");
+        text.push_str("  GET /{scheme}/--bundle     - many templates in one archive (?templates=vim,shell or all,\n");
+        text.push_str("                               &format=zip; tar.gz by default)\n");
+        text.push_str("  GET /base16/{scheme}/...   - pin the system for schemes in both base16 and base24\n");
+        text.push_str(&format!("  GET /base24/{{scheme}}/...     (a bare name prefers {} on this host)\n", profile.default_system));
+        text.push_str("  POST /--preview/{template} - render a scheme YAML/JSON body through a template\n");
+        text.push_str("  POST /--batch              - render a JSON list of {scheme, template, overrides} pairs\n");
         text.push_str("  GET /--random              - redirect to random scheme\n");
         text.push_str("  GET /--random/{template}   - redirect to random scheme with template\n");
+        text.push_str("  GET /--all/{template}.tar.gz - every scheme through one template, with index.json\n");
         text.push_str("  GET /--help                - this help (text/JSON)\n");
+        text.push_str("  GET /--lint                - scheme validation report (JSON/HTML)\n");
+        text.push_str("  GET /--lint/templates      - template key collisions, misclassified config entries,\n");
+        text.push_str("                               compile errors and unresolved variables\n");
+        text.push_str("  GET /--search?q=mono       - autocomplete scheme and template names (JSON, ?limit=)\n");
+        text.push_str("  POST /--validate           - lint a single scheme YAML body (?system=base24)\n");
+        text.push_str("  POST /--from-image         - derive a scheme from a PNG/JPEG (?name=&variant=)\n");
+        text.push_str("  POST /--import?from=FMT    - convert a theme (iterm, windows-terminal, xresources,\n");
+        text.push_str("                               alacritty, kitty, vscode) to a scheme (?system=base24)\n");
         text.push_str("\nFormat selection:\n");
         text.push_str("  ?format=json|yaml|html     - explicit format\n");
+        text.push_str("  ?format=legacy-yaml        - scheme in the legacy flat format (scheme:, base00: \"282a36\")\n");
         text.push_str("  Accept: application/json   - content negotiation\n");
         text.push_str("  Accept: application/yaml\n");
         text.push_str("\nFuzzy matching:\n");
-        text.push_str("  Scheme names are fuzzy matched (e.g. /monoki -> /monokai)\n\n");
+        text.push_str("  Scheme and template names are fuzzy matched (e.g. /monoki -> /monokai)\n");
+        text.push_str("  Templates also answer to aliases (e.g. /monokai/neovim, /monokai/tinted-vim)\n");
+        text.push_str("  Several close matches answer 300 with the candidates; 404s list suggestions\n\n");
 
         text.push_str(&format!("Schemes ({})\n", help.schemes.len()));
         for scheme in &help.schemes {
@@ -939,66 +2028,184 @@ async fn handle_help(
     }
 }
 
-async fn handle_scheme_template(
-    Path(SchemeTemplatePath { scheme, template }): Path<SchemeTemplatePath>
-) -> Response {
-    let sanitized_scheme = sanitize_name(&scheme);
-    let sanitized_template = sanitize_name(&template);
+/// A repo template compiled once, with the variables it interpolates.
+struct CompiledTemplate {
+    template: render::Template,
+    variables: BTreeSet<String>,
+}
 
-    let scheme_info = if let Some(info) = SCHEME_INDEX.find_exact(&sanitized_scheme) {
-        if scheme != info.name {
-            return Redirect::permanent(&format!("/{}/{}", info.name, sanitized_template)).into_response();
-        }
-        info
-    } else if let Some(info) = SCHEME_INDEX.find_fuzzy(&sanitized_scheme, 0.8) {
-        return Redirect::permanent(&format!("/{}/{}", info.name, sanitized_template)).into_response();
-    } else {
-        return (StatusCode::NOT_FOUND, format!("Scheme '{}' not found", sanitized_scheme)).into_response();
+/// Rendered output, and the template's variables the scheme couldn't supply (they
+/// render as empty strings).
+struct Rendered {
+    output: String,
+    missing: Vec<String>,
+}
+
+/// Render a scheme through a template for the given system, as served by `/{scheme}/{template}`.
+fn render_scheme_template(
+    template_info: &TemplateInfo,
+    scheme_data: &SchemeYaml,
+    slug: &str,
+    system: &str,
+    compat: bool,
+) -> Result<Rendered, (StatusCode, String)> {
+    let template_path = match template_info.path_for_system(system) {
+        Some(p) => p,
+        None => return Err((StatusCode::NOT_FOUND, format!("Template '{}' not available for {}", template_info.name, system))),
     };
+    render_template_file(template_path, template_info.escape(slug, system), scheme_data, slug, system, compat)
+}
 
-    let template_info = match TEMPLATE_INDEX.find(&sanitized_template) {
-        Some(info) => info,
-        None => return (StatusCode::NOT_FOUND, format!("Template '{}' not found", sanitized_template)).into_response(),
+fn render_template_file(
+    template_path: &str,
+    escape: render::Escape,
+    scheme_data: &SchemeYaml,
+    slug: &str,
+    system: &str,
+    compat: bool,
+) -> Result<Rendered, (StatusCode, String)> {
+    let compiled = compiled_template(template_path)?;
+    let data = template_context(scheme_data, slug, system, compat).build();
+    let missing = match &data {
+        mustache::Data::Map(map) => render::missing(&compiled.variables, |name| map.contains_key(name))
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
     };
+    let output = compiled.template.render(&data, escape)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to render template {}: {}", template_path, e)))?;
+    Ok(Rendered { output, missing })
+}
 
-    let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
+/// The template at `template_path`, compiled on first use. `{{> name}}` includes
+/// `name.mustache` from the template's own directory. Compile errors name the file and
+/// line.
+fn compiled_template(template_path: &str) -> Result<Arc<CompiledTemplate>, (StatusCode, String)> {
+    if let Some(template) = COMPILED_TEMPLATES.read().unwrap().get(template_path) {
+        return Ok(template.clone());
+    }
+
+    let compile_error = |message: String| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to compile template {}", message));
+    let source = match template_source(template_path) {
         Ok(s) => s,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read scheme file").into_response(),
+        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read template file {}", template_path))),
     };
+    // Partials are only read once every name they're reached by has been vetted
+    let parsed = render::Parsed::parse(&source).map_err(|problem| {
+        let (path, line) = source_line(template_path, problem.line);
+        compile_error(format!("{}:{}: {}", path, line, problem.message))
+    })?;
+    let dir = std::path::Path::new(template_path).parent().unwrap_or(std::path::Path::new("."));
+    let partials = template_partials(template_path, dir, &parsed).map_err(compile_error)?;
+    let template = render::Template::link(parsed, partials);
+    let variables = template.variables();
+
+    let compiled = Arc::new(CompiledTemplate { template, variables });
+    COMPILED_TEMPLATES.write().unwrap().insert(template_path.to_string(), compiled.clone());
+    Ok(compiled)
+}
 
-    let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
-        Ok(d) => d,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
-    };
+/// Every partial and parent `parsed` includes, directly or through other partials, by
+/// name. A partial `name` is `{dir}/{name}.mustache`, and names that would leave `dir`,
+/// by `..`, an absolute path or a symlink, are errors. Missing partials render as nothing.
+fn template_partials(
+    template_path: &str,
+    dir: &std::path::Path,
+    parsed: &render::Parsed,
+) -> Result<HashMap<String, render::Parsed>, String> {
+    let root = dir.canonicalize().map_err(|e| format!("{}: {}", template_path, e))?;
+    let mut pending: Vec<(String, String)> = parsed.includes().into_iter()
+        .map(|(name, line)| {
+            let (path, line) = source_line(template_path, line);
+            (format!("{}:{}", path, line), name.to_string())
+        })
+        .collect();
+    let mut included = HashMap::new();
 
-    let template_path = match template_info.path_for_system(&scheme_info.system) {
-        Some(p) => p,
-        None => return (StatusCode::NOT_FOUND, format!("Template '{}' not available for {}", sanitized_template, scheme_info.system)).into_response(),
-    };
+    while let Some((location, name)) = pending.pop() {
+        if included.contains_key(&name) {
+            continue;
+        }
+        let outside = || format!("{}: partial '{}' is outside {}", location, name, dir.display());
+        if !std::path::Path::new(&name).components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+            return Err(outside());
+        }
+        let path = dir.join(format!("{}.mustache", name));
+        let Ok(resolved) = path.canonicalize() else { continue };
+        if !resolved.starts_with(&root) {
+            return Err(outside());
+        }
+        let source = std::fs::read_to_string(&resolved).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let partial = render::Parsed::parse(&source)
+            .map_err(|problem| format!("{}:{}: {}", path.display(), problem.line, problem.message))?;
+        for (partial_name, line) in partial.includes() {
+            pending.push((format!("{}:{}", path.display(), line), partial_name.to_string()));
+        }
+        included.insert(name, partial);
+    }
+    Ok(included)
+}
 
-    let template_str = match std::fs::read_to_string(template_path) {
-        Ok(s) => {
-            // If using body.mustache, prepend head.mustache if it exists
-            if template_path.ends_with("body.mustache") {
-                let head_path = template_path.replace("body.mustache", "head.mustache");
-                if let Ok(head) = std::fs::read_to_string(&head_path) {
-                    format!("{}\n{}", head, s)
-                } else {
-                    s
-                }
-            } else {
-                s
+/// A template's source; `body.mustache` is rendered with `head.mustache` before it.
+fn template_source(template_path: &str) -> std::io::Result<String> {
+    let body = std::fs::read_to_string(template_path)?;
+    match head_for(template_path).and_then(|head_path| std::fs::read_to_string(head_path).ok()) {
+        Some(head) => Ok(format!("{}\n{}", head, body)),
+        None => Ok(body),
+    }
+}
+
+fn head_for(template_path: &str) -> Option<String> {
+    template_path.ends_with("body.mustache").then(|| template_path.replace("body.mustache", "head.mustache"))
+}
+
+/// The file and line a line of `template_source` comes from.
+fn source_line(template_path: &str, line: usize) -> (String, usize) {
+    let head = head_for(template_path).and_then(|head_path| Some((std::fs::read_to_string(&head_path).ok()?, head_path)));
+    match head {
+        Some((head, head_path)) => {
+            let head_lines = head.split('\n').count();
+            match line <= head_lines {
+                true => (head_path, line),
+                false => (template_path.to_string(), line - head_lines),
             }
         }
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read template file").into_response(),
-    };
+        None => (template_path.to_string(), line),
+    }
+}
 
-    let template_compiled = match mustache::compile_str(&template_str) {
-        Ok(t) => t,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to compile template").into_response(),
+/// Every variable the builder spec provides to templates for `system`.
+fn spec_context_keys(system: &str) -> BTreeSet<String> {
+    let sample = SchemeYaml {
+        system: system.to_string(),
+        name: String::new(),
+        author: String::new(),
+        slug: None,
+        variant: "dark".to_string(),
+        description: None,
+        palette: Slot::for_system(system).iter().map(|slot| (*slot, Rgb::new(0, 0, 0))).collect(),
+        extra: BTreeMap::new(),
     };
+    match template_context(&sample, "sample", system, true).build() {
+        mustache::Data::Map(map) => map.into_keys().collect(),
+        _ => BTreeSet::new(),
+    }
+}
 
-    let slug = scheme_data.slug.as_deref().unwrap_or(&scheme_info.name);
+/// The builder spec's `scheme-*` variables, which extra scheme fields never replace.
+const SPEC_SCHEME_VARIABLES: [&str; 9] = [
+    "scheme-name", "scheme-author", "scheme-description", "scheme-slug", "scheme-slug-underscored",
+    "scheme-system", "scheme-variant", "scheme-is-dark-variant", "scheme-is-light-variant",
+];
+
+/// Mustache variables for a scheme. With `compat` the context is exactly the variable
+/// set defined by the tinted-theming builder spec (0.11) and rendered as the tinted
+/// builder renders it: every scheme variable is present, `scheme-variant` defaults to
+/// "dark", both variant flags are set and decimals have eight places.
+/// Otherwise optional variables are only set when the scheme defines them, and
+/// top-level string/number/bool fields outside the spec are exposed as `scheme-{key}`.
+fn template_context(scheme_data: &SchemeYaml, slug: &str, system: &str, compat: bool) -> MapBuilder {
     let slug_underscored = slug.replace('-', "_");
 
     let mut data = MapBuilder::new()
@@ -1006,74 +2213,558 @@ async fn handle_scheme_template(
         .insert_str("scheme-author", &scheme_data.author)
         .insert_str("scheme-slug", slug)
         .insert_str("scheme-slug-underscored", &slug_underscored)
-        .insert_str("scheme-system", &scheme_info.system);
-
-    if !scheme_data.variant.is_empty() {
-        data = data.insert_str("scheme-variant", &scheme_data.variant);
-        if scheme_data.variant == "dark" {
-            data = data.insert_bool("scheme-is-dark-variant", true);
-        } else if scheme_data.variant == "light" {
-            data = data.insert_bool("scheme-is-light-variant", true);
-        }
-    }
-
-    for (key, value) in &scheme_data.palette {
-        let hex_value = value.trim_start_matches('#');
-        data = data.insert_str(format!("{}-hex", key), hex_value);
-
-        if hex_value.len() == 6 {
-            let hex_r = &hex_value[0..2];
-            let hex_g = &hex_value[2..4];
-            let hex_b = &hex_value[4..6];
-
-            data = data
-                .insert_str(format!("{}-hex-r", key), hex_r)
-                .insert_str(format!("{}-hex-g", key), hex_g)
-                .insert_str(format!("{}-hex-b", key), hex_b)
-                .insert_str(format!("{}-hex-bgr", key), format!("{}{}{}", hex_b, hex_g, hex_r));
-
-            if let (Ok(r), Ok(g), Ok(b)) = (
-                u8::from_str_radix(hex_r, 16),
-                u8::from_str_radix(hex_g, 16),
-                u8::from_str_radix(hex_b, 16),
-            ) {
-                let r16 = (r as u32) * 257;
-                let g16 = (g as u32) * 257;
-                let b16 = (b as u32) * 257;
-
-                data = data
-                    .insert_str(format!("{}-rgb-r", key), r.to_string())
-                    .insert_str(format!("{}-rgb-g", key), g.to_string())
-                    .insert_str(format!("{}-rgb-b", key), b.to_string())
-                    .insert_str(format!("{}-rgb16-r", key), r16.to_string())
-                    .insert_str(format!("{}-rgb16-g", key), g16.to_string())
-                    .insert_str(format!("{}-rgb16-b", key), b16.to_string())
-                    .insert_str(format!("{}-dec-r", key), format!("{:.6}", r as f64 / 255.0))
-                    .insert_str(format!("{}-dec-g", key), format!("{:.6}", g as f64 / 255.0))
-                    .insert_str(format!("{}-dec-b", key), format!("{:.6}", b as f64 / 255.0));
+        .insert_str("scheme-system", system);
+
+    if compat {
+        let variant = if scheme_data.variant.is_empty() { "dark" } else { scheme_data.variant.as_str() };
+        data = data
+            .insert_str("scheme-description", scheme_data.description.as_deref().unwrap_or(""))
+            .insert_str("scheme-variant", variant)
+            .insert_bool("scheme-is-dark-variant", variant == "dark")
+            .insert_bool("scheme-is-light-variant", variant == "light");
+    } else {
+        if let Some(description) = &scheme_data.description {
+            data = data.insert_str("scheme-description", description);
+        }
+        if !scheme_data.variant.is_empty() {
+            data = data.insert_str("scheme-variant", &scheme_data.variant);
+            if scheme_data.variant == "dark" {
+                data = data.insert_bool("scheme-is-dark-variant", true);
+            } else if scheme_data.variant == "light" {
+                data = data.insert_bool("scheme-is-light-variant", true);
+            }
+        }
+        for (key, value) in &scheme_data.extra {
+            let name = format!("scheme-{}", key);
+            if SPEC_SCHEME_VARIABLES.contains(&name.as_str()) {
+                continue;
             }
+            let value = match value {
+                serde_yaml::Value::String(s) => s.clone(),
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+            data = data.insert_str(name, value);
         }
     }
 
-    let rendered = match template_compiled.render_data_to_string(&data.build()) {
-        Ok(r) => r,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render template").into_response(),
-    };
+    // The tinted builder prints eight decimals
+    let decimals = if compat { 8 } else { 6 };
+    for (slot, color) in scheme_data.palette.iter() {
+        let (r, g, b) = (color.r, color.g, color.b);
+        let hex_r = format!("{:02x}", r);
+        let hex_g = format!("{:02x}", g);
+        let hex_b = format!("{:02x}", b);
+        let r16 = (r as u32) * 257;
+        let g16 = (g as u32) * 257;
+        let b16 = (b as u32) * 257;
 
-    Response::builder()
-        .header("content-type", "text/plain; charset=utf-8")
-        .header("content-disposition", format!("attachment; filename=\"{}\"", template_info.download_filename(&slug)))
-        .header("x-scheme-name", &scheme_info.name)
-        .header("x-template-name", &template_info.name)
-        .body(Body::from(rendered))
-        .unwrap()
+        data = data
+            .insert_str(format!("{}-hex", slot), color.hex_digits())
+            .insert_str(format!("{}-hex-bgr", slot), format!("{}{}{}", hex_b, hex_g, hex_r))
+            .insert_str(format!("{}-hex-r", slot), hex_r)
+            .insert_str(format!("{}-hex-g", slot), hex_g)
+            .insert_str(format!("{}-hex-b", slot), hex_b)
+            .insert_str(format!("{}-rgb-r", slot), r.to_string())
+            .insert_str(format!("{}-rgb-g", slot), g.to_string())
+            .insert_str(format!("{}-rgb-b", slot), b.to_string())
+            .insert_str(format!("{}-rgb16-r", slot), r16.to_string())
+            .insert_str(format!("{}-rgb16-g", slot), g16.to_string())
+            .insert_str(format!("{}-rgb16-b", slot), b16.to_string())
+            .insert_str(format!("{}-dec-r", slot), format!("{:.*}", decimals, r as f64 / 255.0))
+            .insert_str(format!("{}-dec-g", slot), format!("{:.*}", decimals, g as f64 / 255.0))
+            .insert_str(format!("{}-dec-b", slot), format!("{:.*}", decimals, b as f64 / 255.0));
+    }
+
+    data
 }
 
-async fn handle_random(Query(query): Query<FormatQuery>) -> Response {
-    use rand::seq::SliceRandom;
-    let scheme = SCHEME_INDEX.names_sorted
-        .choose(&mut rand::thread_rng())
-        .map(|s| s.as_str())
+async fn handle_scheme_template(
+    Path(SchemeTemplatePath { scheme, template }): Path<SchemeTemplatePath>,
+    pin: Option<Extension<SystemPin>>,
+    Query(query): Query<TemplateQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let pin = pin.map(|Extension(p)| p);
+    let (template, archive) = archive::Format::split(&template);
+    let extension = archive.map(|format| format.extension()).unwrap_or_default();
+    let sanitized_template = sanitize_template_name(template);
+
+    // Scheme redirects already carry the canonical template when it's known by name
+    let suffix = match TEMPLATE_INDEX.find(&sanitized_template) {
+        Some(info) => format!("/{}{}", info.name, extension),
+        None => format!("/{}{}", sanitized_template, extension),
+    };
+    let scheme_info = match resolve_scheme(&scheme, pin, &headers, &suffix) {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+
+    let base = format!("{}/{}", pin.map(|SystemPin(s)| format!("/{}", s)).unwrap_or_default(), scheme_info.name);
+    let template_info = match resolve_template(template, &base, extension, &headers, |t| t.path_for_system(&scheme_info.system).is_some()) {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+
+    let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read scheme file").into_response(),
+    };
+
+    let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+        Ok(d) => d,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+    };
+
+    let slug = scheme_data.slug.as_deref().unwrap_or(&scheme_info.name);
+    let compat = query.compat.as_deref() == Some("tinted");
+    if let Some(format) = archive {
+        let slug = slug.to_string();
+        return render_archive(template_info, scheme_data, slug, scheme_info, compat, format).await;
+    }
+    let Rendered { output: rendered, missing } = match render_scheme_template(template_info, &scheme_data, slug, &scheme_info.system, compat) {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+
+    if matches!(query.debug.as_deref(), Some("1" | "true")) {
+        let path = template_info.path_for_system(&scheme_info.system).unwrap_or_default();
+        let variables = compiled_template(path).map(|c| c.variables.clone()).unwrap_or_default();
+        return axum::Json(serde_json::json!({
+            "template": template_info.name,
+            "path": path,
+            "scheme": scheme_info.name,
+            "system": scheme_info.system,
+            "escape": template_info.escape(slug, &scheme_info.system).as_str(),
+            "variables": variables,
+            "missing": missing,
+        })).into_response();
+    }
+
+    let filename = template_info.download_filename(slug, &scheme_info.system);
+    let install_path = template_info.install_path(slug, &scheme_info.system);
+    if query.format.as_deref() == Some("html") {
+        let profile = HOST_PROFILES.for_headers(&headers);
+        let url = format!("/{}/{}", scheme_info.path_on(profile), template_info.name);
+        let mut data = template_context(&scheme_data, slug, &scheme_info.system, false)
+            .insert_str("template-name", &template_info.name)
+            .insert_str("scheme-url", scheme_info.path_on(profile))
+            .insert_str("site-name", &profile.host)
+            .insert_str("filename", &filename)
+            .insert_str("raw-href", format!("{}?download=0", url))
+            .insert_str("download-href", url)
+            .insert_str("highlighted", highlight::highlight(&rendered, filename.rsplit_once('.').map_or("", |(_, ext)| ext)));
+        if let Some(path) = &install_path {
+            data = data.insert_str("install-path", path);
+        }
+        return match PREVIEW_TEMPLATE.render_data_to_string(&data.build()) {
+            Ok(html) => Response::builder()
+                .header("content-type", "text/html; charset=utf-8")
+                .header("x-scheme-name", &scheme_info.name)
+                .header("x-template-name", &template_info.name)
+                .body(Body::from(html))
+                .unwrap(),
+            Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render preview").into_response(),
+        };
+    }
+
+    let disposition = match query.download.as_deref() {
+        Some("0" | "false") => "inline",
+        _ => "attachment",
+    };
+    let mut response = Response::builder()
+        .header("content-type", content_type_for(&filename))
+        .header("content-disposition", format!("{}; filename=\"{}\"", disposition, filename))
+        .header("x-scheme-name", &scheme_info.name)
+        .header("x-template-name", &template_info.name);
+    if let Some(path) = install_path {
+        response = response.header("x-install-path", path);
+    }
+    if !missing.is_empty() {
+        response = response.header("x-missing-variables", missing.join(", "));
+    }
+    response.body(Body::from(rendered)).unwrap()
+}
+
+/// Every file the template's repo defines for the scheme's system, rendered and laid out
+/// by each entry's `filename`. Two entries that install to the same path are an error
+/// rather than an archive missing one of them.
+async fn render_archive(
+    template_info: &'static TemplateInfo,
+    scheme_data: SchemeYaml,
+    slug: String,
+    scheme_info: &'static SchemeInfo,
+    compat: bool,
+    format: archive::Format,
+) -> Response {
+    let archive_slug = slug.clone();
+    let built = tokio::task::spawn_blocking(move || {
+        let (slug, system) = (archive_slug.as_str(), scheme_info.system.as_str());
+        let mut files = Vec::new();
+        let mut entries: HashMap<String, &str> = HashMap::new();
+        for file in TEMPLATE_INDEX.files_for(template_info, system) {
+            let path = file.install_path(slug, system);
+            if let Some(other) = entries.insert(path.clone(), &file.name) {
+                let message = format!("Template entries '{}' and '{}' both install to {}", other, file.name, path);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, message));
+            }
+            let rendered = render_template_file(&file.path, file.escape(slug, system), &scheme_data, slug, system, compat)?;
+            files.push((path, rendered.output));
+        }
+        archive::build(format, &files)
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build archive".to_string()))
+    }).await;
+    let bytes = match built {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(e)) => return e.into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build archive").into_response(),
+    };
+
+    // Key only, without a `{repo}:` qualifier
+    let key = template_info.name.rsplit(':').next().unwrap_or(&template_info.name);
+    Response::builder()
+        .header("content-type", format.content_type())
+        .header("content-disposition", format!("attachment; filename=\"{}-{}{}\"", slug, key, format.extension()))
+        .header("x-scheme-name", &scheme_info.name)
+        .header("x-template-name", &template_info.name)
+        .body(Body::from(bytes))
+        .unwrap()
+}
+
+/// Many templates for one scheme in one archive, each under `{template}/` at its install
+/// path, with a manifest of what was left out and why.
+async fn handle_scheme_bundle(
+    Path(SchemePath { scheme }): Path<SchemePath>,
+    pin: Option<Extension<SystemPin>>,
+    Query(query): Query<BundleQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    let Some(requested) = query.templates.as_deref().filter(|t| !t.trim().is_empty()) else {
+        return (StatusCode::BAD_REQUEST, "Missing ?templates= (comma-separated names, or all)").into_response();
+    };
+    let format = match query.format.as_deref() {
+        None | Some("tar.gz" | "tgz") => archive::Format::TarGz,
+        Some("zip") => archive::Format::Zip,
+        Some(other) => return (StatusCode::BAD_REQUEST, format!("Unknown bundle format '{}' (zip or tar.gz)", other)).into_response(),
+    };
+    let suffix = format!("/--bundle{}", raw_query.map(|q| format!("?{}", q)).unwrap_or_default());
+    let scheme_info = match resolve_scheme(&scheme, pin.map(|Extension(p)| p), &headers, &suffix) {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+    let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read scheme file").into_response(),
+    };
+    let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+        Ok(d) => d,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+    };
+    let slug = scheme_data.slug.clone().unwrap_or_else(|| scheme_info.name.clone());
+    let compat = query.compat.as_deref() == Some("tinted");
+
+    let names: Vec<String> = match requested.trim() {
+        "all" => TEMPLATE_INDEX.sorted_names_for(HOST_PROFILES.for_headers(&headers)),
+        list => list.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect(),
+    };
+    // `templates=all` renders the whole index, so keep it off the async workers
+    let bundle_slug = slug.clone();
+    let built = tokio::task::spawn_blocking(move || {
+        let slug = bundle_slug.as_str();
+        let system = scheme_info.system.as_str();
+        let mut manifest = BundleManifest { scheme: &scheme_info.name, system, templates: Vec::new(), skipped: Vec::new() };
+        let mut files = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for name in names {
+            let Some(info) = TEMPLATE_INDEX.find(&sanitize_template_name(&name)) else {
+                manifest.skipped.push(SkippedTemplate { name, reason: "unknown template".to_string() });
+                continue;
+            };
+            if !seen.insert(info.name.as_str()) {
+                continue;
+            }
+            if info.path_for_system(system).is_none() {
+                manifest.skipped.push(SkippedTemplate { name: info.name.clone(), reason: format!("not available for {}", system) });
+                continue;
+            }
+            match render_scheme_template(info, &scheme_data, slug, system, compat) {
+                Ok(Rendered { output: rendered, .. }) => {
+                    let install_path = info.install_path(slug, system).unwrap_or_else(|| info.download_filename(slug, system));
+                    let path = format!("{}/{}", info.name.replace(':', "-"), install_path);
+                    manifest.templates.push(BundledTemplate { name: info.name.clone(), path: path.clone() });
+                    files.push((path, rendered));
+                }
+                Err((_, message)) => manifest.skipped.push(SkippedTemplate { name: info.name.clone(), reason: message }),
+            }
+        }
+        files.push(("manifest.json".to_string(), serde_json::to_string_pretty(&manifest).unwrap()));
+        archive::build(format, &files)
+    }).await;
+
+    let bytes = match built {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(_)) | Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build archive").into_response(),
+    };
+    Response::builder()
+        .header("content-type", format.content_type())
+        .header("content-disposition", format!("attachment; filename=\"{}-bundle{}\"", slug, format.extension()))
+        .header("x-scheme-name", &scheme_info.name)
+        .body(Body::from(bytes))
+        .unwrap()
+}
+
+async fn handle_scheme_edit(
+    Path(SchemePath { scheme }): Path<SchemePath>,
+    pin: Option<Extension<SystemPin>>,
+    Query(overrides): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let scheme_info = match resolve_scheme(&scheme, pin.map(|Extension(p)| p), &headers, "/--edit") {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+    let profile = HOST_PROFILES.for_headers(&headers);
+
+    let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response(),
+    };
+    let mut scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+        Ok(d) => d,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+    };
+    if scheme_data.system.is_empty() {
+        scheme_data.system = scheme_info.system.clone();
+    }
+    let original = serde_json::to_value(&scheme_data.palette).unwrap();
+
+    // Permalinks carry edited slots as ?base08=ff0000
+    for (key, value) in &overrides {
+        if let (Ok(slot), Some(color)) = (key.parse::<Slot>(), Rgb::from_hex(value))
+            && scheme_data.palette.contains(slot) {
+            scheme_data.palette.insert(slot, color);
+        }
+    }
+
+    let descriptions: HashMap<&str, &str> = scheme_data.palette.iter()
+        .filter_map(|(slot, _)| get_base_description(slot.name()).map(|d| (slot.name(), d)))
+        .collect();
+    let templates = TEMPLATE_INDEX.templates_for_system(&scheme_info.system);
+
+    // JSON is embedded in a <script> block, so keep "</script>" in names from closing it
+    let script_json = |value: &serde_json::Value| value.to_string().replace('<', "\\u003c");
+
+    let data = MapBuilder::new()
+        .insert_str("scheme-name", &scheme_data.name)
+        .insert_str("scheme-author", &scheme_data.author)
+        .insert_str("scheme-system", &scheme_info.system)
+        .insert_str("scheme-url", scheme_info.path_on(profile))
+        .insert_str("scheme-slug", &scheme_info.name)
+        .insert_str("origin", &profile.origin)
+        .insert_str("site-name", &profile.host)
+        .insert_str("scheme-json", script_json(&serde_json::to_value(&scheme_data).unwrap()))
+        .insert_str("original-json", script_json(&original))
+        .insert_str("descriptions-json", script_json(&serde_json::to_value(&descriptions).unwrap()))
+        .insert_vec("slots", |mut vec| {
+            for (slot, color) in scheme_data.palette.iter() {
+                vec = vec.push_map(|map| {
+                    map.insert_str("slot", slot.name())
+                       .insert_str("hex", color.hex_digits())
+                       .insert_str("description", get_base_description(slot.name()).unwrap_or(""))
+                });
+            }
+            vec
+        })
+        .insert_vec("templates", |mut vec| {
+            for template in &templates {
+                vec = vec.push_map(|map| map.insert_str("name", &template.name));
+            }
+            vec
+        })
+        .build();
+
+    let html = match EDIT_TEMPLATE.render_data_to_string(&data) {
+        Ok(h) => h,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render template").into_response(),
+    };
+
+    Response::builder()
+        .header("content-type", "text/html; charset=utf-8")
+        .header("x-scheme-name", &scheme_info.name)
+        .header("x-scheme-system", &scheme_info.system)
+        .body(Body::from(html))
+        .unwrap()
+}
+
+async fn handle_preview(
+    Path(template): Path<String>,
+    body: Bytes,
+) -> Response {
+    let sanitized_template = sanitize_template_name(&template);
+    let template_info = match TEMPLATE_INDEX.find(&sanitized_template) {
+        Some(info) => info,
+        None => return (StatusCode::NOT_FOUND, format!("Template '{}' not found", sanitized_template)).into_response(),
+    };
+
+    // YAML is a superset of JSON, so the editor can post either
+    let scheme_data: SchemeYaml = match serde_yaml::from_slice(&body) {
+        Ok(d) => d,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid scheme: {}", e)).into_response(),
+    };
+    let system = match scheme_data.system.as_str() {
+        "base24" => "base24",
+        _ => "base16",
+    };
+    let slug = sanitize_name(&scheme_data.slug.clone().unwrap_or_else(|| scheme_data.name.to_lowercase().replace(' ', "-")));
+
+    let rendered = match render_scheme_template(template_info, &scheme_data, &slug, system, false) {
+        Ok(r) => r.output,
+        Err(e) => return e.into_response(),
+    };
+
+    Response::builder()
+        .header("content-type", "text/plain; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("x-template-name", &template_info.name)
+        .body(Body::from(rendered))
+        .unwrap()
+}
+
+/// Autocomplete over scheme and template names the host serves.
+async fn handle_search(Query(query): Query<SearchQuery>, headers: HeaderMap) -> Response {
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let limit = query.limit.unwrap_or(10).min(50);
+
+    let schemes: Vec<serde_json::Value> = SCHEME_INDEX.search.search(&query.q)
+        .into_iter()
+        .flat_map(|hit| {
+            // A slug in both systems lists the host's default system first
+            let mut infos: Vec<&SchemeInfo> = SCHEME_INDEX.by_slug[hit.name].iter().map(|id| &SCHEME_INDEX.schemes[id]).collect();
+            infos.sort_by_key(|info| info.system != profile.default_system);
+            infos.into_iter().map(move |info| (hit.clone(), info))
+        })
+        .filter(|(_, info)| profile.serves(&info.system))
+        .take(limit)
+        .map(|(hit, info)| serde_json::json!({
+            "name": hit.name,
+            "system": info.system,
+            "url": format!("/{}", info.path_on(profile)),
+            "kind": hit.kind,
+            "score": round_score(hit.score),
+        }))
+        .collect();
+    // Hits on aliases report the template they stand for, once
+    let mut seen = std::collections::HashSet::new();
+    let templates: Vec<serde_json::Value> = TEMPLATE_INDEX.search.search(&query.q)
+        .into_iter()
+        .filter_map(|hit| Some((TEMPLATE_INDEX.find(hit.name)?, hit)))
+        .filter(|(info, _)| profile.systems.iter().any(|system| info.path_for_system(system).is_some()))
+        .filter(|(info, _)| seen.insert(info.name.as_str()))
+        .take(limit)
+        .map(|(info, hit)| {
+            let mut json = serde_json::json!({
+                "name": info.name,
+                "kind": hit.kind,
+                "score": round_score(hit.score),
+            });
+            if hit.name != info.name {
+                json["alias"] = serde_json::Value::from(hit.name);
+            }
+            json
+        })
+        .collect();
+
+    let json = serde_json::json!({ "query": query.q, "schemes": schemes, "templates": templates });
+    Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&json).unwrap()))
+        .unwrap()
+}
+
+async fn handle_lint(Query(query): Query<FormatQuery>, headers: HeaderMap) -> Response {
+    lint_response(&SCHEME_INDEX.lint, "Scheme lint", "/--lint", query, &headers)
+}
+
+async fn handle_template_lint(Query(query): Query<FormatQuery>, headers: HeaderMap) -> Response {
+    lint_response(&TEMPLATE_INDEX.lint, "Template lint", "/--lint/templates", query, &headers)
+}
+
+fn lint_response(report: &lint::LintReport, title: &str, path: &str, query: FormatQuery, headers: &HeaderMap) -> Response {
+    let wants_html = query.format.as_deref() == Some("html")
+        || (query.format.is_none() && headers.get("accept")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.contains("text/html"))
+            .unwrap_or(false));
+
+    if !wants_html {
+        let json = serde_json::to_string_pretty(report).unwrap();
+        return Response::builder()
+            .header("content-type", "application/json")
+            .header("vary", "Accept")
+            .body(Body::from(json))
+            .unwrap();
+    }
+
+    let data = MapBuilder::new()
+        .insert_str("title", title)
+        .insert_str("json-href", format!("{}?format=json", path))
+        .insert_str("files", report.files.to_string())
+        .insert_str("errors", report.errors.to_string())
+        .insert_str("warnings", report.warnings.to_string())
+        .insert_bool("has-issues", !report.issues.is_empty())
+        .insert_vec("issues", |mut vec| {
+            for issue in &report.issues {
+                vec = vec.push_map(|map| {
+                    map.insert_str("severity", issue.severity.as_str())
+                       .insert_str("kind", issue.kind)
+                       .insert_str("path", &issue.path)
+                       .insert_str("message", &issue.message)
+                });
+            }
+            vec
+        })
+        .build();
+
+    let html = match LINT_TEMPLATE.render_data_to_string(&data) {
+        Ok(h) => h,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render template").into_response(),
+    };
+
+    Response::builder()
+        .header("content-type", "text/html; charset=utf-8")
+        .header("vary", "Accept")
+        .body(Body::from(html))
+        .unwrap()
+}
+
+async fn handle_validate(
+    Query(query): Query<ValidateQuery>,
+    body: Bytes,
+) -> Response {
+    let Ok(yaml) = std::str::from_utf8(&body) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Scheme must be UTF-8 text").into_response();
+    };
+
+    let issues = lint::lint_scheme("<body>", yaml, query.system.as_deref());
+    let valid = !issues.iter().any(|i| i.severity == lint::Severity::Error);
+    let json = serde_json::json!({ "valid": valid, "issues": issues });
+
+    Response::builder()
+        .status(if valid { StatusCode::OK } else { StatusCode::UNPROCESSABLE_ENTITY })
+        .header("content-type", "application/json")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(serde_json::to_string_pretty(&json).unwrap()))
+        .unwrap()
+}
+
+async fn handle_random(Query(query): Query<FormatQuery>, headers: HeaderMap) -> Response {
+    use rand::seq::IteratorRandom;
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let scheme = SCHEME_INDEX.served(profile)
+        .choose(&mut rand::thread_rng())
+        .map(|info| info.path_on(profile))
         .unwrap_or("monokai");
     let order_param = if query.order.as_deref() == Some("color") { "?order=color" } else { "" };
     let location = format!("/{}{}", scheme, order_param);
@@ -1086,13 +2777,22 @@ async fn handle_random(Query(query): Query<FormatQuery>) -> Response {
         .unwrap()
 }
 
-async fn handle_random_template(Path(template): Path<String>) -> Response {
-    use rand::seq::SliceRandom;
-    let scheme = SCHEME_INDEX.names_sorted
+async fn handle_random_template(Path(template): Path<String>, headers: HeaderMap) -> Response {
+    use rand::seq::IteratorRandom;
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let available = |t: &TemplateInfo| profile.systems.iter().any(|system| t.path_for_system(system).is_some());
+    let template_info = match resolve_template(&template, "/--random", "", &headers, available) {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+
+    // Only schemes the template can render
+    let scheme = SCHEME_INDEX.served(profile)
+        .filter(|info| template_info.path_for_system(&info.system).is_some())
         .choose(&mut rand::thread_rng())
-        .map(|s| s.as_str())
+        .map(|info| info.path_on(profile))
         .unwrap_or("monokai");
-    let location = format!("/{}/{}", scheme, template);
+    let location = format!("/{}/{}", scheme, template_info.name);
 
     Response::builder()
         .status(StatusCode::TEMPORARY_REDIRECT)
@@ -1102,25 +2802,283 @@ async fn handle_random_template(Path(template): Path<String>) -> Response {
         .unwrap()
 }
 
-async fn handle_sitemap() -> Response {
-    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>
+/// Every served scheme the template supports, rendered into one `.tar.gz`. Schemes are
+/// rendered one at a time on a blocking thread and streamed through a small pipe, so
+/// memory stays flat however many there are. `index.json` at the end maps each slug
+/// and system to its file.
+async fn handle_all_schemes(Path(template): Path<String>, headers: HeaderMap) -> Response {
+    let (template, format) = archive::Format::split(&template);
+    if format != Some(archive::Format::TarGz) {
+        return (StatusCode::NOT_FOUND, format!("Use /--all/{}.tar.gz", sanitize_template_name(template))).into_response();
+    }
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let available = |t: &TemplateInfo| profile.systems.iter().any(|system| t.path_for_system(system).is_some());
+    let template_info = match resolve_template(template, "/--all", ".tar.gz", &headers, available) {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+    let schemes: Vec<&'static SchemeInfo> = SCHEME_INDEX.served(profile)
+        .filter(|info| template_info.path_for_system(&info.system).is_some())
+        .collect();
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let task = tokio::task::spawn_blocking(move || {
+        let mut tar = archive::TarGzWriter::new(tokio_util::io::SyncIoBridge::new(writer));
+        let mut index = Vec::new();
+        let mut seen = std::collections::HashSet::from(["index.json".to_string()]);
+        for scheme_info in schemes {
+            let Some(scheme_data) = std::fs::read_to_string(&scheme_info.path).ok()
+                .and_then(|yaml| serde_yaml::from_str::<SchemeYaml>(&yaml).ok()) else {
+                continue;
+            };
+            let slug = scheme_data.slug.as_deref().unwrap_or(&scheme_info.name);
+            let system = scheme_info.system.as_str();
+            let rendered = match render_scheme_template(template_info, &scheme_data, slug, system, false) {
+                Ok(rendered) => rendered.output,
+                Err((_, message)) => {
+                    tracing::warn!("Skipping {} in /--all/{}: {}", scheme_info.id, template_info.name, message);
+                    continue;
+                }
+            };
+            // A slug in both systems needs telling apart when the pattern doesn't, and a
+            // pattern that ignores the slug needs numbering
+            let name = template_info.install_path(slug, system).unwrap_or_else(|| template_info.download_filename(slug, system));
+            let file = std::iter::once(name.clone())
+                .chain((1..).map(|n| match n {
+                    1 => format!("{}/{}", system, name),
+                    n => format!("{}/{}/{}", system, n, name),
+                }))
+                .find(|file| seen.insert(file.clone()))
+                .unwrap();
+            tar.append(&file, rendered.as_bytes())?;
+            index.push(ArchivedScheme { slug: slug.to_string(), system: system.to_string(), file });
+        }
+        tar.append("index.json", serde_json::to_string_pretty(&index).unwrap().as_bytes())?;
+        tar.finish()?.shutdown()
+    });
+    // The stream ends once the task drops its writer; a failed task then fails the body
+    // instead of letting a truncated archive pass for a whole one
+    let name = template_info.name.clone();
+    let outcome = futures_util::stream::once(async move {
+        let error = match task.await {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => e,
+            Err(e) => std::io::Error::other(e),
+        };
+        tracing::warn!("/--all/{}.tar.gz failed mid-stream: {}", name, error);
+        Some(Err(error))
+    });
+    let body = ReaderStream::new(reader).chain(outcome.filter_map(std::future::ready));
+
+    let key = template_info.name.rsplit(':').next().unwrap_or(&template_info.name);
+    Response::builder()
+        .header("content-type", archive::Format::TarGz.content_type())
+        .header("content-disposition", format!("attachment; filename=\"{}-all.tar.gz\"", key))
+        .header("x-template-name", &template_info.name)
+        .body(Body::from_stream(body))
+        .unwrap()
+}
+
+/// Render many (scheme, template) pairs in one request. Each scheme is read and parsed
+/// once per batch and templates come from the shared compiled cache; a failing item is
+/// reported under `errors` without failing the rest. Two items with the same key reject
+/// the whole batch, since one would otherwise overwrite the other.
+async fn handle_batch(headers: HeaderMap, body: Bytes) -> Response {
+    let items: Vec<BatchItem> = match serde_json::from_slice(&body) {
+        Ok(items) => items,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid batch: {}", e)).into_response(),
+    };
+    if items.len() > MAX_BATCH_ITEMS {
+        return (StatusCode::PAYLOAD_TOO_LARGE, format!("At most {} items per batch", MAX_BATCH_ITEMS)).into_response();
+    }
+
+    // Items are keyed in the response, so two with the same key would silently collapse
+    let keys: Vec<String> = items.iter()
+        .map(|item| item.id.clone().unwrap_or_else(|| format!("{}/{}", item.scheme, item.template)))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = keys.iter().find(|key| !seen.insert(key.as_str())) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, format!("Duplicate batch key '{}'; give repeated pairs distinct ids", duplicate)).into_response();
+    }
+
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let rendered = tokio::task::spawn_blocking(move || {
+        let mut schemes = HashMap::new();
+        let mut response = BatchResponse::default();
+        for (item, key) in items.iter().zip(keys) {
+            match render_batch_item(item, profile, &mut schemes) {
+                Ok(output) => response.outputs.insert(key, output),
+                Err(message) => response.errors.insert(key, message),
+            };
+        }
+        response
+    }).await;
+    match rendered {
+        Ok(response) => axum::Json(response).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render batch").into_response(),
+    }
+}
+
+fn render_batch_item(
+    item: &BatchItem,
+    profile: &hosts::HostProfile,
+    schemes: &mut HashMap<&'static str, SchemeYaml>,
+) -> Result<String, String> {
+    let (pin, name) = match item.scheme.split_once('/') {
+        Some(("base16", name)) => (Some(SystemPin("base16")), name),
+        Some(("base24", name)) => (Some(SystemPin("base24")), name),
+        _ => (None, item.scheme.as_str()),
+    };
+    let Some(scheme_info) = find_served(&sanitize_name(name), pin, profile) else {
+        let hint = fuzzy_served(&sanitize_name(name), pin, profile, SUGGEST_THRESHOLD).first()
+            .map(|(info, _)| format!(" (did you mean {}?)", info.path_on(profile)))
+            .unwrap_or_default();
+        return Err(format!("Scheme '{}' not found{}", item.scheme, hint));
+    };
+    let template = TEMPLATE_INDEX.find(&sanitize_template_name(&item.template))
+        .ok_or_else(|| format!("Template '{}' not found", item.template))?;
+
+    if !schemes.contains_key(scheme_info.id.as_str()) {
+        let scheme_data = std::fs::read_to_string(&scheme_info.path).ok()
+            .and_then(|yaml| serde_yaml::from_str::<SchemeYaml>(&yaml).ok())
+            .ok_or_else(|| format!("Failed to parse scheme '{}'", scheme_info.id))?;
+        schemes.insert(&scheme_info.id, scheme_data);
+    }
+    let mut scheme_data = std::borrow::Cow::Borrowed(&schemes[scheme_info.id.as_str()]);
+    for (key, value) in &item.overrides {
+        let slot: Slot = key.parse().map_err(|_| format!("Unknown slot '{}'", key))?;
+        let color = Rgb::from_hex(value).ok_or_else(|| format!("Invalid colour '{}' for {}", value, key))?;
+        if !scheme_data.palette.contains(slot) {
+            return Err(format!("{} schemes have no {}", scheme_info.system, key));
+        }
+        scheme_data.to_mut().palette.insert(slot, color);
+    }
+
+    let slug = scheme_data.slug.as_deref().unwrap_or(&scheme_info.name);
+    render_scheme_template(template, &scheme_data, slug, &scheme_info.system, item.compat.as_deref() == Some("tinted"))
+        .map(|rendered| rendered.output)
+        .map_err(|(_, message)| message)
+}
+
+async fn handle_from_image(
+    Query(query): Query<FromImageQuery>,
+    body: Bytes,
+) -> Response {
+    // Decoding and clustering a large image takes a while, so keep it off the async workers
+    let variant = query.variant.clone();
+    let extracted = tokio::task::spawn_blocking(move || {
+        from_image::decode_image(&body).map(|pixels| from_image::extract_palette(&pixels, variant.as_deref()))
+    }).await;
+    let extracted = match extracted {
+        Ok(Ok(extracted)) => extracted,
+        Ok(Err(e)) => return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to extract a palette").into_response(),
+    };
+
+    let name = query.name.as_deref().unwrap_or("Extracted").to_string();
+    let slug = sanitize_name(&name.to_lowercase().replace(' ', "-"));
+    let scheme_data = SchemeYaml {
+        system: "base16".to_string(),
+        name,
+        author: "base16.sh --from-image".to_string(),
+        slug: Some(slug),
+        variant: extracted.variant.to_string(),
+        description: None,
+        palette: extracted.colors.into_iter().collect(),
+        extra: BTreeMap::new(),
+    };
+
+    let preview = build_palette_svg(&scheme_data, 320, 40, 20, false);
+
+    let (content_type, body) = match query.format.as_deref() {
+        Some("svg") => ("image/svg+xml", preview),
+        Some("json") => {
+            let json = serde_json::json!({ "scheme": scheme_data, "preview": preview });
+            ("application/json", serde_json::to_string_pretty(&json).unwrap())
+        }
+        _ => ("application/yaml", serde_yaml::to_string(&scheme_data).unwrap()),
+    };
+
+    Response::builder()
+        .header("content-type", content_type)
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn handle_import(
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Response {
+    let Some(format) = query.from.as_deref() else {
+        return (StatusCode::BAD_REQUEST, format!("Missing ?from=, expected one of: {}", import::FORMATS.join(", "))).into_response();
+    };
+    let Ok(input) = std::str::from_utf8(&body) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Theme file must be UTF-8 text").into_response();
+    };
+    let system = match query.system.as_deref() {
+        Some("base24") => "base24",
+        _ => "base16",
+    };
+
+    let imported = match import::import(format, input, system) {
+        Ok(i) => i,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+    };
+
+    let name = query.name.or(imported.name).unwrap_or_else(|| "Imported".to_string());
+    let slug = sanitize_name(&name.to_lowercase().replace(' ', "-"));
+    let scheme_data = SchemeYaml {
+        system: system.to_string(),
+        name,
+        author: format!("base16.sh --import ({})", format),
+        slug: Some(slug),
+        variant: imported.variant.to_string(),
+        description: None,
+        palette: imported.palette.into_iter().collect(),
+        extra: BTreeMap::new(),
+    };
+
+    let (content_type, body) = match query.format.as_deref() {
+        Some("json") => {
+            let json = serde_json::json!({
+                "scheme": scheme_data,
+                "direct": imported.direct,
+                "inferred": imported.inferred,
+            });
+            ("application/json", serde_json::to_string_pretty(&json).unwrap())
+        }
+        _ => ("application/yaml", serde_yaml::to_string(&scheme_data).unwrap()),
+    };
+
+    Response::builder()
+        .header("content-type", content_type)
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("x-inferred-slots", imported.inferred.iter().map(|slot| slot.name()).collect::<Vec<_>>().join(","))
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn handle_sitemap(headers: HeaderMap) -> Response {
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let mut xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
 <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
   <url>
-    <loc>https://base16.sh/</loc>
+    <loc>{}/</loc>
     <changefreq>daily</changefreq>
     <priority>1.0</priority>
   </url>
-"#);
+"#, profile.origin);
 
-    for scheme_name in &SCHEME_INDEX.names_sorted {
+    for info in SCHEME_INDEX.served(profile) {
         xml.push_str(&format!(
             r#"  <url>
-    <loc>https://base16.sh/{}</loc>
+    <loc>{}/{}</loc>
     <changefreq>weekly</changefreq>
     <priority>0.8</priority>
   </url>
 "#,
-            scheme_name
+            profile.origin, info.path_on(profile)
         ));
     }
 
@@ -1133,10 +3091,9 @@ async fn handle_sitemap() -> Response {
         .unwrap()
 }
 
-async fn handle_robots() -> Response {
-    let robots = format!(
-        "User-agent: *\nAllow: /\n\nSitemap: https://base16.sh/sitemap.xml\n"
-    );
+async fn handle_robots(headers: HeaderMap) -> Response {
+    let origin = &HOST_PROFILES.for_headers(&headers).origin;
+    let robots = format!("User-agent: *\nAllow: /\n\nSitemap: {}/sitemap.xml\n", origin);
 
     Response::builder()
         .header("content-type", "text/plain; charset=utf-8")
@@ -1158,20 +3115,10 @@ async fn handle_favicon() -> Response {
 }
 
 fn build_favicon_svg(scheme_data: &SchemeYaml) -> String {
-    let color_keys = [
-        "base00", "base01", "base02", "base03",
-        "base04", "base05", "base06", "base07",
-        "base08", "base09", "base0A", "base0B",
-        "base0C", "base0D", "base0E", "base0F",
-    ];
-
     let mut svg = String::from(r#"<svg viewBox="0 0 4 4" xmlns="http://www.w3.org/2000/svg" shape-rendering="crispEdges">"#);
 
-    for (i, key) in color_keys.iter().enumerate() {
-        let color = scheme_data.palette.get(*key)
-            .or_else(|| scheme_data.palette.get(&key.to_lowercase()))
-            .map(|s| s.as_str())
-            .unwrap_or("#000000");
+    for (i, slot) in Slot::for_system("base16").iter().enumerate() {
+        let color = scheme_data.palette.hex_or_black(*slot);
         let x = i % 4;
         let y = i / 4;
         svg.push_str(&format!(r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#, x, y, color));
@@ -1181,10 +3128,23 @@ fn build_favicon_svg(scheme_data: &SchemeYaml) -> String {
     svg
 }
 
-async fn handle_scheme_favicon(Path(SchemePath { scheme }): Path<SchemePath>) -> Response {
-    let scheme_info = SCHEME_INDEX.find_exact(&scheme).unwrap();
-    let scheme_yaml_str = std::fs::read_to_string(&scheme_info.path).unwrap();
-    let scheme_data: SchemeYaml = serde_yaml::from_str(&scheme_yaml_str).unwrap();
+async fn handle_scheme_favicon(
+    Path(SchemePath { scheme }): Path<SchemePath>,
+    pin: Option<Extension<SystemPin>>,
+    headers: HeaderMap,
+) -> Response {
+    let scheme_info = match resolve_scheme(&scheme, pin.map(|Extension(p)| p), &headers, "/favicon.svg") {
+        Ok(info) => info,
+        Err(miss) => return miss.into_response(),
+    };
+    let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read scheme file").into_response(),
+    };
+    let scheme_data: SchemeYaml = match serde_yaml::from_str(&scheme_yaml_str) {
+        Ok(d) => d,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse scheme YAML").into_response(),
+    };
 
     Response::builder()
         .header("content-type", "image/svg+xml")
@@ -1193,12 +3153,14 @@ async fn handle_scheme_favicon(Path(SchemePath { scheme }): Path<SchemePath>) ->
         .unwrap()
 }
 
-async fn handle_llms_txt() -> Response {
+async fn handle_llms_txt(headers: HeaderMap) -> Response {
+    let profile = HOST_PROFILES.for_headers(&headers);
+    let served: Vec<&str> = SCHEME_INDEX.served(profile).map(|info| info.path_on(profile)).collect();
     let llms_txt = format!(
-        r#"# base16.sh - Base16/Base24 Color Scheme Distribution
+        r#"# {site} - Base16/Base24 Color Scheme Distribution
 
 ## Description
-base16.sh serves over {} Base16 and Base24 color schemes for terminals, editors, and 70+ applications.
+{site} serves over {count} {systems} color schemes for terminals, editors, and 70+ applications.
 Base16 provides carefully chosen syntax highlighting using a base of sixteen colors.
 Base24 extends this with eight additional accent colors for more expressive themes.
 
@@ -1212,12 +3174,75 @@ Returns: {{"schemes": [...], "templates": [...]}}
 ### Get a specific scheme
 GET /{{scheme}} (Accept: application/yaml)
 GET /{{scheme}}?format=json
+GET /{{scheme}}?format=legacy-yaml
 GET /{{scheme}} (Accept: text/html)
 Returns: Scheme YAML, JSON, or HTML preview with color palette
 
+### Schemes in both systems
+GET /base16/{{scheme}}
+GET /base24/{{scheme}}/{{template}}
+Returns: The scheme from that system. Some slugs (e.g. dracula) exist in both Base16 and Base24; the index lists them as
+base16/{{scheme}} and base24/{{scheme}}, and a bare /{{scheme}} resolves to {default_system} on this host
+
 ### Render scheme through template
 GET /{{scheme}}/{{template}}
-Returns: Rendered config file for the specified application
+GET /{{scheme}}/{{template}}?compat=tinted
+Returns: Rendered config file for the specified application. Templates also see `scheme-description` and
+any extra top-level scheme fields as `scheme-{{key}}`; `compat=tinted` restricts the variables to exactly
+the tinted-theming builder spec set. The download is named after the template's config.yaml `filename`
+pattern, and `X-Install-Path` gives the whole path it describes (e.g. colors/base16-monokai.vim). The content type
+follows the file's extension (JSON, CSS, XML, TOML, YAML, Lua, shell; otherwise text/plain)
+
+GET /{{scheme}}/{{template}}?download=0
+GET /{{scheme}}/{{template}}?format=html
+Returns: The output inline instead of as an attachment, or an HTML page showing it highlighted in the scheme's colors
+
+GET /{{scheme}}/{{template}}?debug=1
+Returns: JSON with the template's path, the variables it references and those missing from the scheme's context.
+Renders with missing variables also carry them in an `X-Missing-Variables` header; compile and render errors name
+the template file and line. Templates may include `{{{{> name}}}}` partials, read as name.mustache from their repo's
+templates/ directory and never from outside it, and extend a parent with `{{{{<parent}}}}{{{{$block}}}}...{{{{/block}}}}{{{{/parent}}}}`.
+Values are escaped for the output's format (JSON strings, XML, shell; none for other configs) rather than as HTML,
+or as the config.yaml entry's `escape: none|html|json|xml|shell` says
+
+GET /{{scheme}}/{{template}}.tar.gz
+GET /{{scheme}}/{{template}}.zip
+Returns: Archive of every file the template's repo config.yaml defines for the scheme's system (say a colors
+file plus a plugin file), each at the path its `filename` describes
+
+GET /{{scheme}}/--bundle?templates=vim,shell,alacritty
+GET /{{scheme}}/--bundle?templates=all&format=zip
+Returns: tar.gz (or zip) with each template rendered under {{template}}/ at its install path, plus manifest.json
+listing the bundled templates and the skipped ones with a reason (unknown, or not available for the scheme's system)
+
+GET /--all/{{template}}.tar.gz
+Returns: Streamed tar.gz with every scheme the template supports, each at its install path, and index.json
+mapping each slug and system to its file
+
+### Edit a scheme
+GET /{{scheme}}/--edit
+GET /{{scheme}}/--edit?base08=ff0000
+Returns: HTML palette editor; query parameters override individual slots
+
+### Render an edited scheme through a template
+POST /--preview/{{template}} (body: scheme YAML or JSON)
+Returns: Rendered config file for the posted palette
+
+### Render many pairs at once
+POST /--batch (body: [{{"scheme": "monokai", "template": "vim"}}, {{"scheme": "base24/dracula", "template": "shell", "id": "dracula-shell", "overrides": {{"base08": "ff0000"}}}}])
+Returns: JSON {{"outputs": {{id: rendered}}, "errors": {{id: message}}}}; ids default to "{{scheme}}/{{template}}" and must be unique. At most {MAX_BATCH_ITEMS} items
+
+### Validate schemes
+GET /--lint (Accept: application/json or text/html)
+POST /--validate?system=base16|base24 (body: scheme YAML)
+Returns: Lint report listing parse failures, missing/extra slots, invalid hex, duplicate colors and system mismatches
+
+GET /--lint/templates
+Returns: Template keys provided by more than one repo. The preferred repo serves /{{scheme}}/{{template}}; any repo's version is reachable as /{{scheme}}/{{repo}}:{{template}}. Also lists config.yaml entries whose supported-systems disagree with their name, whose key isn't derived from their name, or that are skipped, templates that fail to compile (with file and line), and variables a template references that the builder spec doesn't provide for a system it's declared for
+
+### Search
+GET /--search?q=mono&limit=10
+Returns: JSON with matching "schemes" (name, system, url) and "templates", each ranked exact, prefix, substring, then typo matches
 
 ### Random scheme discovery
 GET /--random
@@ -1226,32 +3251,48 @@ Returns: 302 redirect to a random scheme
 GET /--random/{{template}}
 Returns: 302 redirect to a random scheme rendered with the specified template
 
+### Generate a scheme from an image
+POST /--from-image?name=My+Wallpaper (body: PNG or JPEG)
+POST /--from-image?format=json|svg&variant=dark|light
+Returns: Base16 scheme YAML, JSON with an SVG preview, or the SVG preview alone
+
+### Import a theme from another format
+POST /--import?from=iterm|windows-terminal|xresources|alacritty|kitty|vscode (body: theme file)
+POST /--import?from=kitty&system=base24&format=json
+Returns: Scheme YAML (X-Inferred-Slots header lists interpolated slots), or JSON with direct/inferred slot lists
+
 ### Available schemes
-{}
+{schemes}
 
 ### Available templates
-{}
+{templates}
 
 ## Examples
-curl -L base16.sh/monokai/vim
-curl -L base16.sh/dracula/alacritty
-curl -L base16.sh/--random/kitty
-curl -L base16.sh/?format=json | jq .schemes
+curl -L {site}/monokai/vim
+curl -L {site}/dracula/alacritty
+curl -L {site}/--random/kitty
+curl -L {site}/?format=json | jq .schemes
 
 ## Features
-- Fuzzy scheme name matching (e.g., /monoki redirects to /monokai)
+- Fuzzy scheme name matching (e.g., /monoki redirects to /monokai with a temporary redirect)
+- Template aliases and fuzzy matching (e.g., /monokai/tinted-vim and /monokai/vimm redirect to /monokai/vim)
+- Ambiguous names answer 300 Multiple Choices, and 404s list "did you mean" suggestions (JSON with Accept: application/json)
 - Content negotiation via Accept header
 - 70+ application templates (vim, neovim, terminal emulators, tmux, etc.)
 - Both Base16 (16 colors) and Base24 (24 colors) schemes
 
 ## Documentation
-Full API documentation: https://base16.sh/--help
+Full API documentation: {origin}/--help
 GitHub: https://github.com/socksy/base16.sh
 Base16 specification: https://github.com/tinted-theming/home
 "#,
-        SCHEME_INDEX.schemes.len(),
-        SCHEME_INDEX.names_sorted.join(", "),
-        TEMPLATE_INDEX.sorted_names().join(", ")
+        site = profile.host,
+        origin = profile.origin,
+        count = served.len(),
+        systems = profile.systems.iter().map(|s| s.replace("base", "Base")).collect::<Vec<_>>().join(" and "),
+        default_system = profile.default_system,
+        schemes = served.join(", "),
+        templates = TEMPLATE_INDEX.sorted_names_for(profile).join(", "),
     );
 
     Response::builder()
@@ -1276,16 +3317,16 @@ fn build_og_image_svg(scheme_data: &SchemeYaml, scheme_name: &str, _scheme_autho
 
     // Default Dracula palette (what's in the template)
     let dracula_colors = [
-        ("#282a36", "base00"), ("#363447", "base01"), ("#44475a", "base02"), ("#6272a4", "base03"),
-        ("#9ea8c7", "base04"), ("#f8f8f2", "base05"), ("#f0f1f4", "base06"), ("#ffffff", "base07"),
-        ("#ff5555", "base08"), ("#ffb86c", "base09"), ("#f1fa8c", "base0A"), ("#50fa7b", "base0B"),
-        ("#8be9fd", "base0C"), ("#80bfff", "base0D"), ("#ff79c6", "base0E"), ("#bd93f9", "base0F"),
+        ("#282a36", Slot::Base00), ("#363447", Slot::Base01), ("#44475a", Slot::Base02), ("#6272a4", Slot::Base03),
+        ("#9ea8c7", Slot::Base04), ("#f8f8f2", Slot::Base05), ("#f0f1f4", Slot::Base06), ("#ffffff", Slot::Base07),
+        ("#ff5555", Slot::Base08), ("#ffb86c", Slot::Base09), ("#f1fa8c", Slot::Base0A), ("#50fa7b", Slot::Base0B),
+        ("#8be9fd", Slot::Base0C), ("#80bfff", Slot::Base0D), ("#ff79c6", Slot::Base0E), ("#bd93f9", Slot::Base0F),
     ];
 
     // Replace each Dracula color with the scheme's actual color
-    for (dracula_hex, base_key) in dracula_colors {
-        if let Some(scheme_color) = scheme_data.palette.get(base_key) {
-            svg = svg.replace(dracula_hex, scheme_color);
+    for (dracula_hex, slot) in dracula_colors {
+        if let Some(scheme_color) = scheme_data.palette.get(slot) {
+            svg = svg.replace(dracula_hex, &scheme_color.to_hex());
         }
     }
 
@@ -1293,13 +3334,13 @@ fn build_og_image_svg(scheme_data: &SchemeYaml, scheme_name: &str, _scheme_autho
     if is_base24 {
         // Default Dracula Base24 extra colors (reordered to align bright colors with base colors)
         let base24_defaults = [
-            ("#f28c8c", "base12"), ("#1e2029", "base10"), ("#eef5a3", "base13"), ("#a3f5b8", "base14"),
-            ("#baedf7", "base15"), ("#a3ccf5", "base16"), ("#f5a3d2", "base17"), ("#16171d", "base11"),
+            ("#f28c8c", Slot::Base12), ("#1e2029", Slot::Base10), ("#eef5a3", Slot::Base13), ("#a3f5b8", Slot::Base14),
+            ("#baedf7", Slot::Base15), ("#a3ccf5", Slot::Base16), ("#f5a3d2", Slot::Base17), ("#16171d", Slot::Base11),
         ];
 
-        for (default_hex, base_key) in base24_defaults {
-            if let Some(scheme_color) = scheme_data.palette.get(base_key) {
-                svg = svg.replace(default_hex, scheme_color);
+        for (default_hex, slot) in base24_defaults {
+            if let Some(scheme_color) = scheme_data.palette.get(slot) {
+                svg = svg.replace(default_hex, &scheme_color.to_hex());
             }
         }
     }
@@ -1310,32 +3351,37 @@ fn build_og_image_svg(scheme_data: &SchemeYaml, scheme_name: &str, _scheme_autho
     svg
 }
 
-async fn handle_og_image(Path(SchemePath { scheme }): Path<SchemePath>) -> Response {
+async fn handle_og_image(
+    Path(SchemePath { scheme }): Path<SchemePath>,
+    pin: Option<Extension<SystemPin>>,
+    headers: HeaderMap,
+) -> Response {
     let sanitized = sanitize_name(&scheme);
+    let pin = pin.map(|Extension(p)| p);
+    let profile = HOST_PROFILES.for_headers(&headers);
+
+    let scheme_info = match find_served(&sanitized, pin, profile)
+        .or_else(|| fuzzy_served(&sanitized, pin, profile, FUZZY_THRESHOLD).first().map(|(info, _)| *info)) {
+        Some(info) => info,
+        None => return (StatusCode::NOT_FOUND, format!("Scheme '{}' not found", sanitized)).into_response(),
+    };
 
     // Try to use cache if available
-    let cache_dir = std::path::Path::new(".cache/og");
-    let cache_available = std::fs::create_dir_all(cache_dir).is_ok();
-    let cache_path = cache_dir.join(format!("{}.png", sanitized));
+    let cache_dir = std::path::Path::new(".cache/og").join(&scheme_info.system);
+    let cache_available = std::fs::create_dir_all(&cache_dir).is_ok();
+    let cache_path = cache_dir.join(format!("{}.png", scheme_info.name));
 
     // Try to serve from cache if it exists
-    if cache_available && cache_path.exists() {
-        if let Ok(png_data) = std::fs::read(&cache_path) {
+    if cache_available && cache_path.exists()
+        && let Ok(png_data) = std::fs::read(&cache_path) {
             return Response::builder()
                 .header("content-type", "image/png")
                 .header("cache-control", "public, max-age=31536000, immutable")
                 .body(Body::from(png_data))
                 .unwrap();
         }
-    }
 
     // Generate new image
-    let scheme_info = match SCHEME_INDEX.find_exact(&sanitized)
-        .or_else(|| SCHEME_INDEX.find_fuzzy(&sanitized, 0.8)) {
-        Some(info) => info,
-        None => return (StatusCode::NOT_FOUND, format!("Scheme '{}' not found", sanitized)).into_response(),
-    };
-
     let scheme_yaml_str = match std::fs::read_to_string(&scheme_info.path) {
         Ok(s) => s,
         Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read scheme file").into_response(),
@@ -1378,8 +3424,10 @@ async fn handle_og_image(Path(SchemePath { scheme }): Path<SchemePath>) -> Respo
         tracing::warn!("Font directory .cache/fonts does not exist, using system fonts only");
     }
 
-    let mut opt = usvg::Options::default();
-    opt.fontdb = std::sync::Arc::new(fontdb);
+    let opt = usvg::Options {
+        fontdb: std::sync::Arc::new(fontdb),
+        ..Default::default()
+    };
 
     let tree = match usvg::Tree::from_str(&svg_data, &opt) {
         Ok(t) => t,
@@ -1411,21 +3459,52 @@ async fn handle_og_image(Path(SchemePath { scheme }): Path<SchemePath>) -> Respo
         .unwrap()
 }
 
+/// Per-scheme routes pinned to one system, nested under `/base16` and `/base24`.
+fn system_routes(system: &'static str) -> Router {
+    Router::new()
+        .route("/{scheme}/favicon.svg", get(handle_scheme_favicon))
+        .route("/{scheme}/--edit", get(handle_scheme_edit))
+        .route("/{scheme}/--bundle", get(handle_scheme_bundle))
+        .route("/{scheme}/{template}", get(handle_scheme_template))
+        .route("/{scheme}", get(handle_scheme))
+        .layer(Extension(SystemPin(system)))
+}
+
 fn create_app() -> Router {
     Router::new()
         .route("/", get(handle_index))
         .route("/--random", get(handle_random))
         .route("/--random/{template}", get(handle_random_template))
+        .route("/--all/{template}", get(handle_all_schemes))
         .route("/--help", get(handle_help))
+        .route("/--lint", get(handle_lint))
+        .route("/--lint/templates", get(handle_template_lint))
+        .route("/--search", get(handle_search))
         .route("/sitemap.xml", get(handle_sitemap))
         .route("/robots.txt", get(handle_robots))
         .route("/llms.txt", get(handle_llms_txt))
         .route("/favicon.svg", get(handle_favicon))
         .route("/favicon.ico", get(handle_favicon))
         .route("/og/{scheme}", get(handle_og_image))
+        .route("/og/base16/{scheme}", get(handle_og_image).layer(Extension(SystemPin("base16"))))
+        .route("/og/base24/{scheme}", get(handle_og_image).layer(Extension(SystemPin("base24"))))
+        .nest("/base16", system_routes("base16"))
+        .nest("/base24", system_routes("base24"))
         .route("/{scheme}/favicon.svg", get(handle_scheme_favicon))
+        .route("/{scheme}/--edit", get(handle_scheme_edit))
+        .route("/{scheme}/--bundle", get(handle_scheme_bundle))
         .route("/{scheme}/{template}", get(handle_scheme_template))
         .route("/{scheme}", get(handle_scheme))
+        .layer(RequestBodyLimitLayer::new(1024))
+        .merge(
+            Router::new()
+                .route("/--from-image", post(handle_from_image))
+                .route("/--import", post(handle_import))
+                .route("/--preview/{template}", post(handle_preview))
+                .route("/--validate", post(handle_validate))
+                .route("/--batch", post(handle_batch))
+                .layer(RequestBodyLimitLayer::new(UPLOAD_LIMIT))
+        )
         .layer(SetResponseHeaderLayer::if_not_present(
             header::X_CONTENT_TYPE_OPTIONS,
             HeaderValue::from_static("nosniff"),
@@ -1450,17 +3529,108 @@ fn create_app() -> Router {
             header::CACHE_CONTROL,
             HeaderValue::from_static("public, max-age=3600"),
         ))
-        .layer(RequestBodyLimitLayer::new(1024))
+}
+
+/// `base16-server lint`: print the scheme lint report and exit non-zero on errors.
+fn run_lint_cli() -> i32 {
+    let report = &SCHEME_INDEX.lint;
+    for issue in &report.issues {
+        println!("{}: {} [{}] {}", issue.severity.as_str(), issue.path, issue.kind, issue.message);
+    }
+    println!("{} files, {} errors, {} warnings", report.files, report.errors, report.warnings);
+    if report.errors > 0 { 1 } else { 0 }
+}
+
+/// Render every template file for every scheme of a system it supports.
+fn check_matrix() -> matrix::Report {
+    let mut report = matrix::Report::default();
+    let schemes: Vec<(&SchemeInfo, SchemeYaml)> = SCHEME_INDEX.names_sorted.iter()
+        .map(|id| &SCHEME_INDEX.schemes[id])
+        .filter_map(|info| {
+            let yaml = std::fs::read_to_string(&info.path).ok()?;
+            Some((info, serde_yaml::from_str(&yaml).ok()?))
+        })
+        .collect();
+    report.schemes = schemes.len();
+
+    let mut repos: Vec<_> = TEMPLATE_INDEX.files.keys().collect();
+    repos.sort();
+    for repo in repos {
+        for file in &TEMPLATE_INDEX.files[repo] {
+            report.templates += 1;
+            let template = format!("{}/{}", repo, file.name);
+            if let Err((_, message)) = compiled_template(&file.path) {
+                report.push(&template, &file.path, lint::Severity::Error, "compile-error", message, None);
+                continue;
+            }
+            for (info, scheme_data) in schemes.iter().filter(|(info, _)| file.provides(&info.system)) {
+                report.renders += 1;
+                let slug = scheme_data.slug.as_deref().unwrap_or(&info.name);
+                let mut problem = |severity, kind, message| {
+                    report.push(&template, &file.path, severity, kind, message, Some(&info.id));
+                };
+                let escape = file.escape(slug, &info.system);
+                let rendered = match render_template_file(&file.path, escape, scheme_data, slug, &info.system, false) {
+                    Ok(rendered) => rendered,
+                    Err((_, message)) => {
+                        problem(lint::Severity::Error, "render-error", message);
+                        continue;
+                    }
+                };
+                if !rendered.missing.is_empty() {
+                    problem(lint::Severity::Warning, "unresolved-variables", format!("No value for {}", rendered.missing.join(", ")));
+                }
+                if rendered.output.trim().is_empty() {
+                    problem(lint::Severity::Error, "empty-output", "Rendered nothing".to_string());
+                } else if let Some(message) = matrix::parse_error(&file.install_path(slug, &info.system), &rendered.output) {
+                    problem(lint::Severity::Error, "invalid-output", message);
+                }
+            }
+        }
+    }
+    report
+}
+
+/// `check-matrix [report.json]`: write the coverage report and exit non-zero on errors.
+fn run_check_matrix_cli(output: &str) -> i32 {
+    let report = check_matrix();
+    for problem in &report.problems {
+        println!("{}: {} [{}] {} ({} schemes)", problem.severity.as_str(), problem.template, problem.kind, problem.message, problem.schemes.len());
+    }
+    println!(
+        "{} templates x {} schemes: {} renders, {} errors, {} warnings",
+        report.templates, report.schemes, report.renders, report.errors, report.warnings,
+    );
+    if let Err(e) = std::fs::write(output, serde_json::to_string_pretty(&report).unwrap()) {
+        eprintln!("Failed to write {}: {}", output, e);
+        return 2;
+    }
+    println!("Report written to {}", output);
+    if report.errors > 0 { 1 } else { 0 }
 }
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        std::process::exit(run_lint_cli());
+    }
+    if std::env::args().nth(1).as_deref() == Some("check-matrix") {
+        let output = std::env::args().nth(2).unwrap_or_else(|| "check-matrix.json".to_string());
+        std::process::exit(run_check_matrix_cli(&output));
+    }
+
+    aliases::enable_tracking();
     Lazy::force(&SCHEME_INDEX);
     Lazy::force(&TEMPLATE_INDEX);
+    Lazy::force(&HOST_PROFILES);
     Lazy::force(&INDEX_TEMPLATE);
     Lazy::force(&SCHEME_TEMPLATE);
+    Lazy::force(&EDIT_TEMPLATE);
+    Lazy::force(&LINT_TEMPLATE);
+    Lazy::force(&CHOICES_TEMPLATE);
+    Lazy::force(&PREVIEW_TEMPLATE);
     Lazy::force(&OG_IMAGE_TEMPLATE);
 
     let app = create_app();
@@ -1511,21 +3681,21 @@ mod tests {
 
     #[test]
     fn test_scheme_fuzzy_match_typo() {
-        let info = SCHEME_INDEX.find_fuzzy("monoki", 0.8);
+        let info = SCHEME_INDEX.fuzzy_slugs("monoki", 0.8, None).first().map(|(slug, _)| *slug);
         assert!(info.is_some(), "Should fuzzy match 'monoki' to 'monokai'");
-        assert_eq!(info.unwrap().name, "monokai");
+        assert_eq!(info.unwrap(), "monokai");
     }
 
     #[test]
     fn test_scheme_fuzzy_match_partial() {
-        let info = SCHEME_INDEX.find_fuzzy("dracula", 0.8);
+        let info = SCHEME_INDEX.fuzzy_slugs("dracula", 0.8, None).first().map(|(slug, _)| *slug);
         assert!(info.is_some());
-        assert_eq!(info.unwrap().name, "dracula");
+        assert_eq!(info.unwrap(), "dracula");
     }
 
     #[test]
     fn test_scheme_fuzzy_no_match_garbage() {
-        let info = SCHEME_INDEX.find_fuzzy("xyzzy123", 0.8);
+        let info = SCHEME_INDEX.fuzzy_slugs("xyzzy123", 0.8, None).first().map(|(slug, _)| *slug);
         assert!(info.is_none(), "Should not match random garbage");
     }
 
@@ -1614,87 +3784,660 @@ mod tests {
             )
             .await
             .unwrap();
-
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["name"], "Monokai");
+    }
+
+    #[tokio::test]
+    async fn test_scheme_json_palette_is_canonical() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai?format=json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let content = String::from_utf8(body.to_vec()).unwrap();
+        let positions: Vec<usize> = Slot::for_system("base16").iter()
+            .map(|slot| content.find(&format!("\"{}\"", slot)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        let hex = Regex::new(r"^#[0-9a-f]{6}$").unwrap();
+        assert!(json["palette"].as_object().unwrap().values().all(|v| hex.is_match(v.as_str().unwrap())));
+    }
+
+    #[tokio::test]
+    async fn test_scheme_fuzzy_redirect() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monoki").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // A guess, so temporary and uncached
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers().get("cache-control").unwrap(), "no-cache");
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/monokai"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scheme_redirect_with_trailing_punctuation() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai%20.!").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/monokai"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_endpoint() {
+        let response = create_app()
+            .oneshot(Request::builder().uri("/--search?q=Dra").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["schemes"][0]["name"], "dracula");
+        assert_eq!(json["schemes"][0]["system"], "base16");
+        assert_eq!(json["schemes"][0]["kind"], "prefix");
+
+        let json: serde_json::Value = serde_json::from_str(&body_for_host("/--search?q=vim&limit=1", "base16.sh").await).unwrap();
+        assert_eq!(json["templates"][0]["kind"], "exact");
+        assert_eq!(json["templates"].as_array().unwrap().len(), 1);
+
+        // Typos are matched too, and base24.sh only offers base24 schemes
+        let json: serde_json::Value = serde_json::from_str(&body_for_host("/--search?q=drcaula", "base24.sh").await).unwrap();
+        assert_eq!(json["schemes"][0]["url"], "/dracula");
+        assert_eq!(json["schemes"][0]["kind"], "fuzzy");
+        assert_eq!(json["schemes"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_likely_matches() {
+        assert_eq!(likely_matches(&[]), 0);
+        assert_eq!(likely_matches(&[0.7, 0.65]), 0);
+        assert_eq!(likely_matches(&[0.9, 0.85, 0.7]), 1);
+        assert_eq!(likely_matches(&[0.95, 0.94, 0.81, 0.7]), 3);
+    }
+
+    #[tokio::test]
+    async fn test_scheme_not_found_suggestions() {
+        let response = create_app()
+            .oneshot(Request::builder().uri("/dark").header("accept", "application/json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["url"], "/dracula");
+        assert!(json[0]["score"].as_f64().unwrap() < FUZZY_THRESHOLD);
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/dark").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Did you mean:\n  /dracula"));
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_scheme_choices() {
+        let choices = || vec![
+            Choice { name: "gruvbox-dark-hard".into(), system: Some("base16".into()), url: "/gruvbox-dark-hard".into(), score: 0.94 },
+            Choice { name: "gruvbox-dark-soft".into(), system: Some("base16".into()), url: "/gruvbox-dark-soft".into(), score: 0.94 },
+        ];
+        let ambiguous = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("accept", HeaderValue::from_str(accept).unwrap());
+            Miss::Ambiguous(Suggestions::new("Scheme", "gruvbox-dark", choices(), &headers)).into_response()
+        };
+
+        let response = ambiguous("application/json");
+        assert_eq!(response.status(), StatusCode::MULTIPLE_CHOICES);
+        assert_eq!(response.headers().get("cache-control").unwrap(), "no-cache");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+
+        let body = to_bytes(ambiguous("text/html").into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(r#"<a href="/gruvbox-dark-soft">"#));
+
+        let body = to_bytes(ambiguous("*/*").into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).starts_with("Scheme 'gruvbox-dark' matches several schemes"));
+    }
+
+    #[tokio::test]
+    async fn test_scheme_not_found() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/xyzzy123456").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_template_endpoint() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai/vim").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("x-scheme-name").unwrap(),
+            "monokai"
+        );
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let content = String::from_utf8(body.to_vec()).unwrap();
+        assert!(content.contains("monokai"), "Template should contain scheme name");
+    }
+
+    #[tokio::test]
+    async fn test_template_not_found() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai/nonexistent").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn redirect_for(uri: &str) -> (StatusCode, String) {
+        let response = create_app()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let location = response.headers().get("location").map(|v| v.to_str().unwrap().to_string());
+        (response.status(), location.unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_template_aliases_and_fuzzy() {
+        // Other spellings and repo-derived aliases redirect permanently to the key
+        assert_eq!(redirect_for("/monokai/Alacritty").await, (StatusCode::PERMANENT_REDIRECT, "/monokai/alacritty".to_string()));
+        assert_eq!(redirect_for("/monokai/tinted-vim").await, (StatusCode::PERMANENT_REDIRECT, "/monokai/vim".to_string()));
+        assert_eq!(redirect_for("/base24/dracula/base16-shell").await, (StatusCode::PERMANENT_REDIRECT, "/base24/dracula/shell".to_string()));
+        // Scheme and template fixed in one hop
+        assert_eq!(redirect_for("/monoki/tinted-vim").await, (StatusCode::TEMPORARY_REDIRECT, "/monokai/vim".to_string()));
+
+        assert_eq!(redirect_for("/monokai/alacrity").await, (StatusCode::TEMPORARY_REDIRECT, "/monokai/alacritty".to_string()));
+        assert_eq!(redirect_for("/--random/tinted-vim").await, (StatusCode::PERMANENT_REDIRECT, "/--random/vim".to_string()));
+        assert_eq!(redirect_for("/--random/alacrity").await, (StatusCode::TEMPORARY_REDIRECT, "/--random/alacritty".to_string()));
+        assert_eq!(redirect_for("/--random/qqqqq").await.0, StatusCode::NOT_FOUND);
+
+        assert_eq!(TEMPLATE_INDEX.find("TINTED-VIM").map(|t| t.name.as_str()), Some("vim"));
+    }
+
+    fn source(repo: &str, key: &str, variant: &'static str) -> TemplateSource {
+        TemplateSource {
+            key: key.to_string(),
+            repo: repo.to_string(),
+            variant,
+            path: format!("data/templates/{}/templates/{}.mustache", repo, variant),
+            filename: None,
+            escape: None,
+        }
+    }
+
+    #[test]
+    fn test_template_key_collisions() {
+        let sources = || vec![
+            source("base16-kitty", "kitty", "base16"),
+            source("base24-kitty", "kitty", "base24"),
+            source("base16-vim", "vim", "base16"),
+            source("tinted-vim", "vim", "both"),
+            source("tinted-terminal", "foot", "both"),
+            source("base16-foot", "foot", "base16"),
+        ];
+
+        let (templates, qualified, lint) = TemplateIndex::reconcile(sources(), &[]);
+        // A base16-/base24- pair is one template, not a collision
+        assert_eq!(templates["kitty"].repos, vec!["base16-kitty", "base24-kitty"]);
+        assert!(templates["kitty"].base24_path.as_deref().unwrap().contains("base24-kitty"));
+        // tinted-* wins by default; the others stay addressable
+        assert_eq!(templates["vim"].repos, vec!["tinted-vim"]);
+        assert_eq!(templates["foot"].repos, vec!["tinted-terminal"]);
+        assert_eq!(qualified["base16-vim:vim"].repos, vec!["base16-vim"]);
+        assert_eq!(lint.issues.len(), 2);
+        assert!(lint.issues.iter().all(|i| i.kind == "template-collision"));
+
+        // A configured preference takes the short key, borrowing slots it lacks from its own family
+        let (templates, _, _) = TemplateIndex::reconcile(sources(), &["base16-vim".to_string(), "base16-foot".to_string()]);
+        assert!(templates["vim"].base16_path.as_deref().unwrap().contains("base16-vim"));
+        assert!(templates["vim"].base24_path.as_deref().unwrap().contains("tinted-vim"));
+        assert_eq!(templates["foot"].repos, vec!["base16-foot"]);
+        assert!(templates["foot"].base24_path.is_none());
+    }
+
+    #[test]
+    fn test_supported_systems_override_name_heuristics() {
+        let entry = |systems: Option<&[&str]>| TemplateConfigEntry {
+            supported_systems: systems.map(|s| s.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        };
+        let mut notes = Vec::new();
+        let mut classify = |repo: &str, name: &str, count: usize, systems: Option<&[&str]>| {
+            let short = repo.trim_start_matches("base16-").trim_start_matches("base24-");
+            classify_template(repo, short, name, count, &entry(systems), &mut notes)
+        };
+
+        // Older configs: the name decides
+        let key = |key: &str, variant| Some((key.to_string(), variant));
+        assert_eq!(classify("tinted-vim", "base24", 2, None), key("vim", "base24"));
+        assert_eq!(classify("base24-kitty", "default", 1, None), key("kitty", "base24"));
+        // The declaration wins over the name, and the disagreement is reported
+        assert_eq!(classify("tinted-vim", "base16", 2, Some(&["base16", "base24"])), key("vim", "both"));
+        assert_eq!(classify("base16-kitty", "default", 1, Some(&["base24"])), key("kitty", "base24"));
+        assert_eq!(classify("base16-kitty", "default", 1, Some(&["base16"])), key("kitty", "base16"));
+        // Declared for other systems only: skipped, and reported
+        assert_eq!(classify("tinted-vim", "base16", 2, Some(&["tinted8"])), None);
+        // No key from the name: declared entries are served as {repo}-{name}, others skipped
+        assert_eq!(classify("tinted-nvim", "base16.vim", 2, Some(&["base16"])), key("nvim-base16-vim", "base16"));
+        assert_eq!(classify("tinted-vim", "colors", 3, None), None);
+
+        assert_eq!(notes.len(), 5);
+        assert!(notes.iter().all(|n| n.kind == "template-classification"));
+        assert!(notes[0].message.contains("declares supported-systems base16, base24"));
+        assert_eq!(notes[0].path, "tinted-vim/templates/config.yaml");
+        assert!(notes[2].message.contains("'base16' declares supported-systems tinted8, none of which"));
+        assert!(notes[3].message.contains("'base16.vim'") && notes[3].message.contains("serving it as nvim-base16-vim"));
+        assert!(notes[4].message.contains("'colors'"));
+    }
+
+    #[test]
+    fn test_filename_patterns() {
+        let entry: TemplateConfigEntry = serde_yaml::from_str("filename: \"colors/{{ scheme-system }}-{{scheme-slug}}.vim\"").unwrap();
+        let pattern = entry.filename_pattern().unwrap();
+        assert_eq!(render_filename(&pattern, "base24", "dracula"), "colors/base24-dracula.vim");
+        // Older configs describe the same path with output and extension
+        let entry: TemplateConfigEntry = serde_yaml::from_str("extension: .sh\noutput: scripts/").unwrap();
+        assert_eq!(render_filename(&entry.filename_pattern().unwrap(), "base16", "monokai"), "scripts/base16-monokai.sh");
+        assert!(TemplateConfigEntry::default().filename_pattern().is_none());
+        // Paths stay inside the install directory
+        assert_eq!(render_filename("/../{{ scheme-slug }}/./x.conf", "base16", ".."), "x.conf");
+        assert_eq!(render_filename("{{ unknown }}{{ scheme-slug }}.conf", "base16", "a"), "a.conf");
+    }
+
+    #[tokio::test]
+    async fn test_download_filename_and_install_path() {
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/vim").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"base16-monokai.vim\"");
+        assert_eq!(response.headers()["x-install-path"], "colors/base16-monokai.vim");
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/base24/dracula/shell").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-install-path"], "scripts/base24-dracula.sh");
+    }
+
+    #[test]
+    fn test_archive_files_follow_the_slot_repo() {
+        let file = |name: &str, variant, filename: &str| TemplateFile {
+            name: name.to_string(),
+            variant,
+            path: format!("data/templates/tinted-gtk/templates/{}.mustache", name),
+            filename: Some(filename.to_string()),
+            escape: None,
+        };
+        let index = TemplateIndex {
+            templates: HashMap::new(),
+            qualified: HashMap::new(),
+            aliases: HashMap::new(),
+            search: search::NameIndex::default(),
+            lint: lint::LintReport::default(),
+            files: [("tinted-gtk".to_string(), vec![
+                file("base16", "base16", "gtk-3.0/{{ scheme-system }}-{{ scheme-slug }}.css"),
+                file("settings", "base16", "settings.ini"),
+                file("base24", "base24", "gtk-3.0/{{ scheme-system }}-{{ scheme-slug }}.css"),
+            ])].into_iter().collect(),
+        };
+        let mut info = TemplateInfo::from_sources("gtk".to_string(), std::iter::empty());
+        info.base16_path = Some(index.files["tinted-gtk"][0].path.clone());
+
+        let paths = |info: &TemplateInfo, system| index.files_for(info, system).iter()
+            .map(|f| f.install_path("nord", system))
+            .collect::<Vec<_>>();
+        assert_eq!(paths(&info, "base16"), vec!["gtk-3.0/base16-nord.css", "settings.ini"]);
+        // Without a base24 slot, base24 schemes get the base16 set
+        assert_eq!(paths(&info, "base24"), vec!["gtk-3.0/base24-nord.css", "settings.ini"]);
+        info.base24_path = Some(index.files["tinted-gtk"][2].path.clone());
+        assert_eq!(paths(&info, "base24"), vec!["gtk-3.0/base24-nord.css"]);
+    }
+
+    #[tokio::test]
+    async fn test_template_archives() {
+        use std::io::Read;
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/vim.zip").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/zip");
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"monokai-vim.zip\"");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(body.to_vec())).unwrap();
+        assert_eq!(zip.len(), 1);
+        let mut contents = String::new();
+        zip.by_name("colors/base16-monokai.vim").unwrap().read_to_string(&mut contents).unwrap();
+        assert!(contents.contains("272822"));
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/base24/dracula/vim.tar.gz").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(body.as_ref()));
+        let paths: Vec<String> = tar.entries().unwrap().map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(paths, vec!["colors/base24-dracula.vim"]);
+
+        // Redirects keep the archive extension
+        assert_eq!(redirect_for("/monokai/Vim.tar.gz").await, (StatusCode::PERMANENT_REDIRECT, "/monokai/vim.tar.gz".to_string()));
+        assert_eq!(redirect_for("/monokia/Vim.zip").await.1, "/monokai/vim.zip");
+    }
+
+    #[tokio::test]
+    async fn test_scheme_bundle() {
+        use std::io::Read;
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/--bundle?templates=vim,bash,nope,Vim&format=zip").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(
-            response.headers().get("content-type").unwrap(),
-            "application/json"
-        );
-
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"monokai-bundle.zip\"");
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(json["name"], "Monokai");
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(body.to_vec())).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["manifest.json", "shell/scripts/base16-monokai.sh", "vim/colors/base16-monokai.vim"]);
+        let mut manifest = String::new();
+        zip.by_name("manifest.json").unwrap().read_to_string(&mut manifest).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest["system"], "base16");
+        assert_eq!(manifest["templates"][1]["name"], "shell");
+        assert_eq!(manifest["skipped"], serde_json::json!([{ "name": "nope", "reason": "unknown template" }]));
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/base24/dracula/--bundle?templates=all").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/gzip");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(body.as_ref()));
+        let count = tar.entries().unwrap().count();
+        assert_eq!(count, TEMPLATE_INDEX.templates_for_system("base24").len() + 1);
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/--bundle").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        // Scheme redirects keep the query
+        assert_eq!(redirect_for("/Monokai/--bundle?templates=vim").await, (StatusCode::PERMANENT_REDIRECT, "/monokai/--bundle?templates=vim".to_string()));
     }
 
     #[tokio::test]
-    async fn test_scheme_fuzzy_redirect() {
-        let app = create_app();
-        let response = app
-            .oneshot(Request::builder().uri("/monoki").body(Body::empty()).unwrap())
+    async fn test_all_schemes_archive() {
+        use std::io::Read;
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/--all/vim.tar.gz").body(Body::empty()).unwrap())
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
-        assert_eq!(
-            response.headers().get("location").unwrap(),
-            "/monokai"
-        );
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"vim-all.tar.gz\"");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(body.as_ref()));
+        let mut files = Vec::new();
+        let mut index = String::new();
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            if path == "index.json" {
+                entry.read_to_string(&mut index).unwrap();
+            }
+            files.push(path);
+        }
+        let index: Vec<serde_json::Value> = serde_json::from_str(&index).unwrap();
+        assert_eq!(files.len(), index.len() + 1);
+        assert_eq!(index.len(), SCHEME_INDEX.schemes.len());
+        // dracula is in both systems; each gets its own file
+        let dracula: Vec<&str> = index.iter().filter(|s| s["slug"] == "dracula").map(|s| s["file"].as_str().unwrap()).collect();
+        assert_eq!(dracula.len(), 2);
+        assert!(dracula.contains(&"colors/base24-dracula.vim"));
+        assert!(index.iter().all(|s| files.contains(&s["file"].as_str().unwrap().to_string())));
+
+        assert_eq!(redirect_for("/--all/VIM.tar.gz").await, (StatusCode::PERMANENT_REDIRECT, "/--all/vim.tar.gz".to_string()));
+        assert_eq!(redirect_for("/--all/vim.zip").await.0, StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn test_scheme_redirect_with_trailing_punctuation() {
-        let app = create_app();
-        let response = app
-            .oneshot(Request::builder().uri("/monokai%20.!").body(Body::empty()).unwrap())
+    async fn test_batch_render() {
+        let batch = serde_json::json!([
+            { "scheme": "monokai", "template": "vim" },
+            { "scheme": "base24/dracula", "template": "Shell", "id": "dracula-shell", "overrides": { "base00": "ff0000" } },
+            { "scheme": "monokia", "template": "vim" },
+            { "scheme": "monokai", "template": "nope" },
+            { "scheme": "monokai", "template": "vim", "id": "bad", "overrides": { "base12": "ff0000" } },
+        ]);
+        let response = create_app()
+            .oneshot(Request::builder().method("POST").uri("/--batch").body(Body::from(batch.to_string())).unwrap())
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
-        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
-        assert_eq!(
-            response.headers().get("location").unwrap(),
-            "/monokai"
-        );
+        let single = create_app()
+            .oneshot(Request::builder().uri("/monokai/vim").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let single = to_bytes(single.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(json["outputs"]["monokai/vim"], String::from_utf8_lossy(&single).as_ref());
+        assert!(json["outputs"]["dracula-shell"].as_str().unwrap().contains("ff/00/00"));
+        assert_eq!(json["errors"]["monokia/vim"], "Scheme 'monokia' not found (did you mean monokai?)");
+        assert_eq!(json["errors"]["monokai/nope"], "Template 'nope' not found");
+        assert_eq!(json["errors"]["bad"], "base16 schemes have no base12");
+        assert!(COMPILED_TEMPLATES.read().unwrap().keys().any(|path| path.contains("tinted-vim")));
+
+        let response = create_app()
+            .oneshot(Request::builder().method("POST").uri("/--batch").body(Body::from("{")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let duplicates = serde_json::json!([
+            { "scheme": "monokai", "template": "vim" },
+            { "scheme": "monokai", "template": "vim", "overrides": { "base00": "ff0000" } },
+        ]);
+        let response = create_app()
+            .oneshot(Request::builder().method("POST").uri("/--batch").body(Body::from(duplicates.to_string())).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("'monokai/vim'"));
     }
 
     #[tokio::test]
-    async fn test_scheme_not_found() {
-        let app = create_app();
-        let response = app
-            .oneshot(Request::builder().uri("/xyzzy123456").body(Body::empty()).unwrap())
-            .await
-            .unwrap();
+    async fn test_template_content_types_and_preview() {
+        let get = |uri: &'static str| async move {
+            create_app().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap()
+        };
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = get("/monokai/alacritty").await;
+        assert_eq!(response.headers()["content-type"], "application/toml; charset=utf-8");
+        assert!(response.headers()["content-disposition"].to_str().unwrap().starts_with("attachment;"));
+        assert_eq!(get("/monokai/shell").await.headers()["content-type"], "text/x-shellscript; charset=utf-8");
+
+        let response = get("/monokai/vim?download=0").await;
+        assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(response.headers()["content-disposition"], "inline; filename=\"base16-monokai.vim\"");
+
+        let response = get("/monokai/vim?format=html").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/html; charset=utf-8");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8_lossy(&body);
+        assert!(html.contains("--bg: #272822;"));
+        assert!(html.contains("<pre id=\"output\">"));
+        assert!(html.contains("href=\"/monokai/vim?download=0\""));
+        assert!(html.contains("colors/base16-monokai.vim"));
+
+        assert_eq!(content_type_for("theme.JSON"), "application/json; charset=utf-8");
+        assert_eq!(content_type_for("index.html"), "text/plain; charset=utf-8");
+        assert_eq!(content_type_for("monokai.vim"), "text/plain; charset=utf-8");
     }
 
     #[tokio::test]
-    async fn test_template_endpoint() {
-        let app = create_app();
-        let response = app
-            .oneshot(Request::builder().uri("/monokai/vim").body(Body::empty()).unwrap())
+    async fn test_template_debug() {
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/shell?debug=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["template"], "shell");
+        assert_eq!(json["system"], "base16");
+        assert_eq!(json["escape"], "shell");
+        assert!(json["variables"].as_array().unwrap().contains(&serde_json::json!("base00-hex-r")));
+        assert_eq!(json["missing"], serde_json::json!([]));
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/monokai/shell").body(Body::empty()).unwrap())
             .await
             .unwrap();
+        assert!(!response.headers().contains_key("x-missing-variables"));
+    }
 
-        assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(
-            response.headers().get("x-scheme-name").unwrap(),
-            "monokai"
-        );
+    #[test]
+    fn test_template_diagnostics() {
+        let dir = std::env::temp_dir().join(format!("base16-diagnostics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, source: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, source).unwrap();
+            path.to_string_lossy().to_string()
+        };
+        write("head.mustache", "# {{scheme-name}}\n");
+        let body = write("body.mustache", "ok\n{{#scheme-is-dark-variant}}\n{{base00-hex}}\n");
+        let extra = write("extra.mustache", "{{base00-hex}} {{base10-hex}} {{ scheme-nickname }}");
+        let (_, message) = compiled_template(&body).err().unwrap();
+        assert_eq!(message, format!("Failed to compile template {}:2: unclosed section 'scheme-is-dark-variant'", body));
+
+        let file = |path: &str, variant| TemplateFile { name: "x".to_string(), variant, path: path.to_string(), filename: None, escape: None };
+        let files = HashMap::from([("repo".to_string(), vec![file(&body, "base16"), file(&extra, "both")])]);
+        let issues = TemplateIndex::check_files(&files);
+        assert_eq!(issues.len(), 3);
+        assert_eq!((issues[0].kind, issues[0].severity), ("template-compile-error", lint::Severity::Error));
+        assert_eq!(issues[1].kind, "template-unresolved-variables");
+        assert!(issues[1].message.ends_with("base10-hex, scheme-nickname with no value for base16 schemes"));
+        assert!(issues[2].message.ends_with("scheme-nickname with no value for base24 schemes"));
+
+        let scheme: SchemeYaml = serde_yaml::from_str(&std::fs::read_to_string("data/schemes/base16/monokai.yaml").unwrap()).unwrap();
+        let rendered = render_template_file(&extra, render::Escape::None, &scheme, "monokai", "base16", false).unwrap();
+        assert_eq!(rendered.output, "272822  ");
+        assert_eq!(rendered.missing, ["base10-hex", "scheme-nickname"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let content = String::from_utf8(body.to_vec()).unwrap();
-        assert!(content.contains("monokai"), "Template should contain scheme name");
+    #[test]
+    fn test_template_partials_are_sandboxed() {
+        let root = std::env::temp_dir().join(format!("base16-partials-{}", std::process::id()));
+        let dir = root.join("repo/templates");
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        let write = |path: &std::path::Path, source: &str| {
+            std::fs::write(path, source).unwrap();
+            path.to_string_lossy().to_string()
+        };
+        write(&root.join("secret.mustache"), "secret");
+        write(&dir.join("shared/colors.mustache"), "bg={{base00-hex}}{{> shared/name}}");
+        write(&dir.join("shared/name.mustache"), " ({{scheme-name}})");
+        let main = write(&dir.join("base16.mustache"), "{{> shared/colors}}{{> missing}}");
+        let scheme: SchemeYaml = serde_yaml::from_str(&std::fs::read_to_string("data/schemes/base16/monokai.yaml").unwrap()).unwrap();
+        let rendered = render_template_file(&main, render::Escape::None, &scheme, "monokai", "base16", false).unwrap();
+        assert_eq!(rendered.output, "bg=272822 (Monokai)");
+        assert_eq!(compiled_template(&main).unwrap().variables.iter().collect::<Vec<_>>(), ["base00-hex", "scheme-name"]);
+
+        let escape = write(&dir.join("escape.mustache"), "x\n{{> ../../secret}}");
+        let (_, message) = compiled_template(&escape).err().unwrap();
+        assert_eq!(message, format!("Failed to compile template {}:2: partial '../../secret' is outside {}", escape, dir.display()));
+        let absolute = write(&dir.join("absolute.mustache"), &format!("{{{{> {}}}}}", root.join("secret").display()));
+        assert!(compiled_template(&absolute).err().unwrap().1.contains("is outside"));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret.mustache"), dir.join("link.mustache")).unwrap();
+            let nested = write(&dir.join("nested.mustache"), "{{> shared/via-link}}");
+            write(&dir.join("shared/via-link.mustache"), "{{> link}}");
+            let (_, message) = compiled_template(&nested).err().unwrap();
+            assert!(message.contains("via-link.mustache:1: partial 'link' is outside"), "{}", message);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_check_matrix() {
+        let report = check_matrix();
+        assert_eq!(report.schemes, SCHEME_INDEX.schemes.len());
+        assert_eq!(report.templates, TEMPLATE_INDEX.files.values().map(Vec::len).sum::<usize>());
+        assert!(report.renders >= report.schemes);
+        assert_eq!(report.errors, 0, "{:#?}", report.problems);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["errors"], 0);
+        assert!(json.get("grouped").is_none());
     }
 
     #[tokio::test]
-    async fn test_template_not_found() {
-        let app = create_app();
-        let response = app
-            .oneshot(Request::builder().uri("/monokai/nonexistent").body(Body::empty()).unwrap())
+    async fn test_repo_qualified_template() {
+        let (status, _) = redirect_for("/monokai/tinted-vim:vim").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(redirect_for("/monokai/Tinted-Vim:vim").await, (StatusCode::PERMANENT_REDIRECT, "/monokai/tinted-vim:vim".to_string()));
+        // No such repo: the alias-like name is close enough to guess the short key
+        assert_eq!(redirect_for("/monokai/base16-vim:vim").await, (StatusCode::TEMPORARY_REDIRECT, "/monokai/vim".to_string()));
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/--lint/templates").body(Body::empty()).unwrap())
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["files"], TEMPLATE_INDEX.templates.len());
     }
 
     #[tokio::test]
@@ -1926,6 +4669,346 @@ mod tests {
         assert_eq!(parse_tinted_template_name("base15", "vim"), None);
     }
 
+    #[tokio::test]
+    async fn test_from_image_yaml() {
+        let mut pixmap = Pixmap::new(8, 8).unwrap();
+        pixmap.fill(tiny_skia::Color::from_rgba8(40, 42, 54, 255));
+        let png = pixmap.encode_png().unwrap();
+
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--from-image?name=Night%20Sky")
+                    .body(Body::from(png))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let scheme: SchemeYaml = serde_yaml::from_slice(&body).unwrap();
+        assert_eq!(scheme.name, "Night Sky");
+        assert_eq!(scheme.slug.as_deref(), Some("night-sky"));
+        assert_eq!(scheme.variant, "dark");
+        assert!(Slot::for_system("base16").iter().all(|slot| scheme.palette.contains(*slot)));
+    }
+
+    #[tokio::test]
+    async fn test_from_image_rejects_non_image() {
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--from-image")
+                    .body(Body::from("not an image"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_import_endpoint() {
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--import?from=kitty&format=json")
+                    .body(Body::from("background #000000\nforeground #ffffff\ncolor1 #ff0000\n"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("x-inferred-slots").unwrap().to_str().unwrap().contains("base01"));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["scheme"]["palette"]["base08"], "#ff0000");
+        assert_eq!(json["scheme"]["variant"], "dark");
+        assert!(json["direct"].as_array().unwrap().contains(&serde_json::json!("base08")));
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_non_ascii_colour() {
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--import?from=kitty")
+                    .body(Body::from("background #aééb\nforeground #aéééb\n"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_scheme_edit_page_applies_permalink() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai/--edit?base08=00ff00&base99=123456").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(r##"data-slot="base08" value="#00ff00""##));
+        assert!(!html.contains("base99"));
+    }
+
+    #[tokio::test]
+    async fn test_preview_renders_posted_scheme() {
+        let scheme = r##"{"system": "base16", "name": "Posted", "author": "Me", "palette": {"base00": "#123456", "base08": "#abcdef"}}"##;
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--preview/vim")
+                    .body(Body::from(scheme))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let content = String::from_utf8(body.to_vec()).unwrap();
+        assert!(content.contains("123456"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_endpoint() {
+        let app = create_app();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/--validate?system=base16")
+                    .body(Body::from("system: base16\nname: X\nauthor: Y\npalette:\n  base00: \"#zzzzzz\"\n"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["valid"], false);
+        assert!(json["issues"].as_array().unwrap().iter().any(|i| i["kind"] == "invalid-hex"));
+    }
+
+    #[tokio::test]
+    async fn test_lint_endpoint_json() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/--lint").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["files"].as_u64().unwrap() as usize, SCHEME_INDEX.lint.files);
+        assert!(json["issues"].is_array());
+    }
+
+    #[test]
+    fn test_scheme_with_bad_colour_still_loads() {
+        let yaml = "system: base16\nname: Typo\nauthor: Me\npalette:\n  base00: \"#282a36\"\n  base01: \"#zz3447\"\n  base02: [1]\n";
+        let scheme: SchemeYaml = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(scheme.palette.get(Slot::Base00), Some(Rgb::new(0x28, 0x2a, 0x36)));
+        assert!(!scheme.palette.contains(Slot::Base01));
+        assert!(!scheme.palette.contains(Slot::Base02));
+    }
+
+    #[test]
+    fn test_legacy_scheme_format() {
+        let legacy = "scheme: \"Legacy\"\nauthor: \"Someone\"\nbase00: \"282a36\"\nbase01: 363447\nbase0A: \"#F1FA8C\"\n";
+        let scheme: SchemeYaml = serde_yaml::from_str(legacy).unwrap();
+        assert!(SchemeYaml::is_legacy(legacy));
+        assert!(SchemeYaml::is_legacy("name: Legacy\nauthor: Someone\nbase00: \"282a36\"\n"));
+        assert!(!SchemeYaml::is_legacy("name: X\nauthor: Y\npalette:\n  base00: \"282a36\"\n"));
+        assert_eq!(scheme.name, "Legacy");
+        assert_eq!(scheme.system, "base16");
+        assert_eq!(scheme.palette.get(Slot::Base00), Some(Rgb::new(0x28, 0x2a, 0x36)));
+        assert_eq!(scheme.palette.get(Slot::Base01), Some(Rgb::new(0x36, 0x34, 0x47)));
+        assert_eq!(scheme.palette.get(Slot::Base0A), Some(Rgb::new(0xf1, 0xfa, 0x8c)));
+
+        let roundtrip: SchemeYaml = serde_yaml::from_str(&scheme.to_legacy_yaml()).unwrap();
+        assert_eq!(roundtrip.palette, scheme.palette);
+        assert!(scheme.to_legacy_yaml().contains("base00: \"282a36\""));
+    }
+
+    #[test]
+    fn test_scheme_yaml_requires_palette() {
+        assert!(serde_yaml::from_str::<SchemeYaml>("name: X\nauthor: Y\n").is_err());
+        assert!(serde_yaml::from_str::<SchemeYaml>("palette:\n  base00: \"#000000\"\nauthor: Y\n").is_err());
+    }
+
+    fn fixture_template() -> TemplateInfo {
+        TemplateInfo {
+            name: "fixture".to_string(),
+            base16_path: Some(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tinted-builder/template.mustache").to_string()),
+            base24_path: None,
+            base16_filename: None,
+            base24_filename: None,
+            base16_escape: None,
+            base24_escape: None,
+            repos: vec!["fixture".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_tinted_compat_matches_fixture() {
+        let scheme: SchemeYaml = serde_yaml::from_str(include_str!("../tests/fixtures/tinted-builder/scheme.yaml")).unwrap();
+        let rendered = render_scheme_template(&fixture_template(), &scheme, "fixture-scheme", "base16", true).unwrap().output;
+        assert_eq!(rendered, include_str!("../tests/fixtures/tinted-builder/expected.txt"));
+    }
+
+    #[test]
+    fn test_template_escape_modes() {
+        let mut scheme: SchemeYaml = serde_yaml::from_str(include_str!("../tests/fixtures/tinted-builder/scheme.yaml")).unwrap();
+        scheme.name = "Tom & \"Jerry\"".to_string();
+        let render = |info: &TemplateInfo| render_scheme_template(info, &scheme, "fixture-scheme", "base16", true).unwrap().output;
+        // Plain configs get values as they are
+        let mut info = fixture_template();
+        assert_eq!(info.escape("fixture-scheme", "base16"), render::Escape::None);
+        assert!(render(&info).starts_with("scheme-name: Tom & \"Jerry\"\n"));
+        // The output's extension picks the mode, and config.yaml's `escape` wins over it
+        info.base16_filename = Some("themes/{{ scheme-slug }}.json".to_string());
+        assert_eq!(info.escape("fixture-scheme", "base16"), render::Escape::Json);
+        assert!(render(&info).starts_with("scheme-name: Tom & \\\"Jerry\\\"\n"));
+        info.base16_escape = Some(render::Escape::Html);
+        assert!(render(&info).starts_with("scheme-name: Tom &amp; &quot;Jerry&quot;\n"));
+    }
+
+    #[test]
+    fn test_scheme_yaml_preserves_unknown_fields() {
+        let mut scheme: SchemeYaml = serde_yaml::from_str(include_str!("../tests/fixtures/tinted-builder/scheme.yaml")).unwrap();
+        let json = serde_json::to_value(&scheme).unwrap();
+        assert_eq!(json["description"], "Reference scheme for the tinted builder compatibility test");
+        assert_eq!(json["homepage"], "https://base16.sh");
+
+        let roundtrip: SchemeYaml = serde_yaml::from_str(&serde_yaml::to_string(&scheme).unwrap()).unwrap();
+        assert_eq!(roundtrip.extra, scheme.extra);
+
+        let rendered = render_scheme_template(&fixture_template(), &scheme, "fixture-scheme", "base16", false).unwrap().output;
+        assert!(rendered.contains("scheme-homepage: https://base16.sh\n"));
+        assert!(rendered.contains("scheme-variant: \n"));
+
+        // Extra fields never replace a spec variable
+        scheme.extra.insert("slug-underscored".to_string(), "oops".into());
+        let rendered = render_scheme_template(&fixture_template(), &scheme, "fixture-scheme", "base16", false).unwrap().output;
+        assert!(rendered.contains("scheme-slug-underscored: fixture_scheme\n"));
+    }
+
+    #[tokio::test]
+    async fn test_scheme_endpoint_legacy_yaml() {
+        let app = create_app();
+        let response = app
+            .oneshot(Request::builder().uri("/monokai?format=legacy-yaml").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let content = String::from_utf8(body.to_vec()).unwrap();
+        assert!(content.starts_with("scheme: \"Monokai\""));
+        assert!(!content.contains("palette:"));
+    }
+
+    async fn scheme_system(uri: &str, host: Option<&str>) -> (StatusCode, Option<String>) {
+        let mut request = Request::builder().uri(uri);
+        if let Some(host) = host {
+            request = request.header("host", host);
+        }
+        let response = create_app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let system = response.headers().get("x-scheme-system").map(|v| v.to_str().unwrap().to_string());
+        (response.status(), system)
+    }
+
+    #[tokio::test]
+    async fn test_system_qualified_schemes() {
+        // dracula exists as both base16/dracula.yaml and base24/dracula.yaml
+        assert_eq!(scheme_system("/base16/dracula", None).await, (StatusCode::OK, Some("base16".to_string())));
+        assert_eq!(scheme_system("/base24/dracula", None).await, (StatusCode::OK, Some("base24".to_string())));
+        assert_eq!(scheme_system("/dracula", None).await, (StatusCode::OK, Some("base16".to_string())));
+        assert_eq!(scheme_system("/dracula", Some("base24.sh")).await, (StatusCode::OK, Some("base24".to_string())));
+        assert_eq!(scheme_system("/base24/monokai", None).await.0, StatusCode::NOT_FOUND);
+
+        let response = create_app()
+            .oneshot(Request::builder().uri("/base24/draculaa/vim").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers().get("location").unwrap(), "/base24/dracula/vim");
+
+        assert!(SCHEME_INDEX.names_sorted.contains(&"base16/dracula".to_string()));
+        assert!(SCHEME_INDEX.names_sorted.contains(&"base24/dracula".to_string()));
+        assert!(SCHEME_INDEX.names_sorted.contains(&"monokai".to_string()));
+    }
+
+    async fn body_for_host(uri: &str, host: &str) -> String {
+        let response = create_app()
+            .oneshot(Request::builder().uri(uri).header("host", host).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_host_profiles() {
+        // base24.sh only serves base24 schemes
+        assert_eq!(scheme_system("/monokai", Some("base24.sh")).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(scheme_system("/one-dark", Some("base24.sh")).await, (StatusCode::OK, Some("base24".to_string())));
+
+        let json: serde_json::Value = serde_json::from_str(&body_for_host("/?format=json", "base24.sh").await).unwrap();
+        let schemes: Vec<&str> = json["schemes"].as_array().unwrap().iter().map(|s| s.as_str().unwrap()).collect();
+        // Single-system hosts link bare slugs even where the slug is shared with base16
+        assert!(schemes.contains(&"dracula"));
+        assert!(!schemes.contains(&"monokai"));
+        assert_eq!(scheme_system("/dracula", Some("base24.sh")).await, (StatusCode::OK, Some("base24".to_string())));
+
+        assert!(body_for_host("/robots.txt", "base24.sh").await.contains("Sitemap: https://base24.sh/sitemap.xml"));
+        let sitemap = body_for_host("/sitemap.xml", "base24.sh").await;
+        assert!(sitemap.contains("<loc>https://base24.sh/one-dark</loc>"));
+        assert!(!sitemap.contains("base16.sh"));
+        assert!(body_for_host("/llms.txt", "www.base24.sh").await.starts_with("# base24.sh"));
+
+        // Unknown hosts get the first (base16.sh) profile
+        assert!(body_for_host("/robots.txt", "localhost:3000").await.contains("https://base16.sh/sitemap.xml"));
+    }
+
+    #[tokio::test]
+    async fn test_scheme_miss_responses() {
+        let response = Miss::Renamed("/base24/dracula/vim".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers().get("location").unwrap(), "/base24/dracula/vim");
+
+        let response = Miss::Gone { name: "old".to_string(), successor: Some("/monokai".to_string()) }.into_response();
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert_eq!(response.headers().get("link").unwrap(), "</monokai>; rel=\"successor-version\"");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Try /monokai"));
+
+        let response = Miss::Gone { name: "old".to_string(), successor: None }.into_response();
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert!(response.headers().get("link").is_none());
+    }
+
     #[tokio::test]
     async fn test_random_template_redirect() {
         let app = create_app();
@@ -1943,8 +5026,7 @@ mod tests {
         let location = response.headers().get("location").unwrap().to_str().unwrap();
         assert!(location.starts_with("/"));
         assert!(location.ends_with("/vim"));
-        let parts: Vec<&str> = location.split('/').collect();
-        assert_eq!(parts.len(), 3);
-        assert!(SCHEME_INDEX.schemes.contains_key(parts[1]));
+        let id = location.trim_start_matches('/').trim_end_matches("/vim");
+        assert!(SCHEME_INDEX.schemes.contains_key(id));
     }
 }
@@ -0,0 +1,105 @@
+//! The template × scheme coverage report behind `check-matrix`: every template file
+//! rendered for every scheme of a system it supports, with failures, empty outputs,
+//! unresolved variables and outputs that don't parse as their extension's format.
+//! Problems are grouped by template and message, listing the schemes they affect.
+
+use crate::lint::Severity;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub templates: usize,
+    pub schemes: usize,
+    pub renders: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub problems: Vec<Problem>,
+    #[serde(skip)]
+    grouped: HashMap<(String, &'static str, String), usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    /// `{repo}/{config entry}`.
+    pub template: String,
+    pub path: String,
+    pub severity: Severity,
+    pub kind: &'static str,
+    pub message: String,
+    /// Scheme ids; empty when the template fails before any scheme is involved.
+    pub schemes: Vec<String>,
+}
+
+impl Report {
+    /// Record a problem, merging it into an earlier one with the same template, kind
+    /// and message.
+    pub fn push(&mut self, template: &str, path: &str, severity: Severity, kind: &'static str, message: String, scheme: Option<&str>) {
+        match severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+        let key = (template.to_string(), kind, message);
+        let index = match self.grouped.get(&key) {
+            Some(&index) => index,
+            None => {
+                self.problems.push(Problem {
+                    template: key.0.clone(),
+                    path: path.to_string(),
+                    severity,
+                    kind,
+                    message: key.2.clone(),
+                    schemes: Vec::new(),
+                });
+                self.grouped.insert(key, self.problems.len() - 1);
+                self.problems.len() - 1
+            }
+        };
+        if let Some(scheme) = scheme {
+            self.problems[index].schemes.push(scheme.to_string());
+        }
+    }
+}
+
+/// Why `output` doesn't parse as the format `filename`'s extension names, if it's one
+/// that can be checked: JSON, TOML, YAML, XML or plist.
+pub fn parse_error(filename: &str, output: &str) -> Option<String> {
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase())?;
+    let result = match extension.as_str() {
+        "json" => serde_json::from_str::<serde_json::Value>(output).map(drop).map_err(|e| format!("Invalid JSON: {}", e)),
+        "toml" => toml::from_str::<toml::Value>(output).map(drop).map_err(|e| format!("Invalid TOML: {}", e.message())),
+        "yaml" | "yml" => serde_yaml::from_str::<serde_yaml::Value>(output).map(drop).map_err(|e| format!("Invalid YAML: {}", e)),
+        "plist" | "tmtheme" => plist::from_bytes::<plist::Value>(output.as_bytes()).map(drop).map_err(|e| format!("Invalid plist: {}", e)),
+        "xml" | "xaml" | "svg" => roxmltree::Document::parse(output).map(drop).map_err(|e| format!("Invalid XML: {}", e)),
+        _ => return None,
+    };
+    result.err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(parse_error("colors.toml", "[colors.primary]\nbackground = \"#272822\"\n"), None);
+        assert!(parse_error("colors.toml", "[colors\n").unwrap().starts_with("Invalid TOML"));
+        assert!(parse_error("theme.JSON", "{\"a\": 1,}").unwrap().starts_with("Invalid JSON"));
+        assert!(parse_error("theme.yml", "a: [1").unwrap().starts_with("Invalid YAML"));
+        assert!(parse_error("theme.xml", "<a><b></a>").unwrap().starts_with("Invalid XML"));
+        assert!(parse_error("monokai.tmTheme", "<plist><dict><key>a</key></plist>").unwrap().starts_with("Invalid plist"));
+        assert_eq!(parse_error("colors.vim", "{ not json"), None);
+        assert_eq!(parse_error("Makefile", ""), None);
+    }
+
+    #[test]
+    fn test_problems_group_by_message() {
+        let mut report = Report::default();
+        report.push("tinted-vim/base16", "a.mustache", Severity::Warning, "empty-output", "Rendered nothing".to_string(), Some("monokai"));
+        report.push("tinted-vim/base16", "a.mustache", Severity::Warning, "empty-output", "Rendered nothing".to_string(), Some("dracula"));
+        report.push("tinted-vim/base24", "b.mustache", Severity::Warning, "empty-output", "Rendered nothing".to_string(), Some("dracula"));
+        assert_eq!(report.warnings, 3);
+        assert_eq!(report.problems.len(), 2);
+        assert_eq!(report.problems[0].schemes, ["monokai", "dracula"]);
+    }
+}
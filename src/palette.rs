@@ -0,0 +1,161 @@
+//! Typed scheme palette: Base16/Base24 slots mapped to parsed colours.
+//!
+//! Slot names are matched case-insensitively and colours accept `#rgb`, `#rrggbb`
+//! and `#rrggbbaa` with or without the `#`. Serialization always produces slots in
+//! canonical order with lowercase `#rrggbb` values, so output is stable.
+
+use crate::color::Rgb;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Slot {
+    Base00, Base01, Base02, Base03, Base04, Base05, Base06, Base07,
+    Base08, Base09, Base0A, Base0B, Base0C, Base0D, Base0E, Base0F,
+    Base10, Base11, Base12, Base13, Base14, Base15, Base16, Base17,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 24] = [
+        Slot::Base00, Slot::Base01, Slot::Base02, Slot::Base03, Slot::Base04, Slot::Base05, Slot::Base06, Slot::Base07,
+        Slot::Base08, Slot::Base09, Slot::Base0A, Slot::Base0B, Slot::Base0C, Slot::Base0D, Slot::Base0E, Slot::Base0F,
+        Slot::Base10, Slot::Base11, Slot::Base12, Slot::Base13, Slot::Base14, Slot::Base15, Slot::Base16, Slot::Base17,
+    ];
+
+    const NAMES: [&'static str; 24] = [
+        "base00", "base01", "base02", "base03", "base04", "base05", "base06", "base07",
+        "base08", "base09", "base0A", "base0B", "base0C", "base0D", "base0E", "base0F",
+        "base10", "base11", "base12", "base13", "base14", "base15", "base16", "base17",
+    ];
+
+    /// Slots a scheme of the given system must define.
+    pub fn for_system(system: &str) -> &'static [Slot] {
+        match system {
+            "base24" => &Slot::ALL,
+            _ => &Slot::ALL[..16],
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Canonical spelling, e.g. "base0A".
+    pub fn name(self) -> &'static str {
+        Slot::NAMES[self.index()]
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Slot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl FromStr for Slot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Slot::NAMES.iter()
+            .position(|name| name.eq_ignore_ascii_case(s))
+            .map(|i| Slot::ALL[i])
+            .ok_or_else(|| format!("unknown palette slot '{}'", s))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette(BTreeMap<Slot, Rgb>);
+
+impl Palette {
+    pub fn get(&self, slot: Slot) -> Option<Rgb> {
+        self.0.get(&slot).copied()
+    }
+
+    /// `#rrggbb` for the slot, or black when the scheme doesn't define it.
+    pub fn hex_or_black(&self, slot: Slot) -> String {
+        self.get(slot).unwrap_or(Rgb::new(0, 0, 0)).to_hex()
+    }
+
+    pub fn insert(&mut self, slot: Slot, color: Rgb) {
+        self.0.insert(slot, color);
+    }
+
+    pub fn contains(&self, slot: Slot) -> bool {
+        self.0.contains_key(&slot)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Slots and colours in canonical order.
+    pub fn iter(&self) -> impl Iterator<Item = (Slot, Rgb)> + '_ {
+        self.0.iter().map(|(s, c)| (*s, *c))
+    }
+
+    /// Build from raw `slot -> hex` strings as found in scheme files. Keys that aren't
+    /// slots and colours that don't parse are skipped; the linter reports both.
+    pub fn from_strings(raw: HashMap<String, String>) -> Palette {
+        raw.into_iter()
+            .filter_map(|(key, value)| Some((key.parse::<Slot>().ok()?, Rgb::from_hex(&value)?)))
+            .collect()
+    }
+}
+
+impl FromIterator<(Slot, Rgb)> for Palette {
+    fn from_iter<I: IntoIterator<Item = (Slot, Rgb)>>(iter: I) -> Self {
+        Palette(iter.into_iter().collect())
+    }
+}
+
+impl Serialize for Palette {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (slot, color) in &self.0 {
+            map.serialize_entry(slot.name(), &color.to_hex())?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_parse_case_insensitive() {
+        assert_eq!("base0A".parse::<Slot>(), Ok(Slot::Base0A));
+        assert_eq!("BASE0a".parse::<Slot>(), Ok(Slot::Base0A));
+        assert_eq!(Slot::Base0A.name(), "base0A");
+        assert!("base18".parse::<Slot>().is_err());
+    }
+
+    #[test]
+    fn test_palette_serializes_in_canonical_order() {
+        let raw: HashMap<String, String> = [("base0a", "ABC"), ("base10", "#000000"), ("base00", "#FFFFFF"), ("base09", "123456")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let palette = Palette::from_strings(raw);
+        let json = serde_json::to_string(&palette).unwrap();
+        assert_eq!(json, r##"{"base00":"#ffffff","base09":"#123456","base0A":"#aabbcc","base10":"#000000"}"##);
+    }
+
+    #[test]
+    fn test_palette_skips_unknown_slots_and_bad_colours() {
+        let raw: HashMap<String, String> = [("accent", "#ffffff"), ("base00", "#fffff"), ("base01", "#ffffff")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let palette = Palette::from_strings(raw);
+        assert_eq!(palette.iter().collect::<Vec<_>>(), [(Slot::Base01, Rgb::new(255, 255, 255))]);
+    }
+}
@@ -0,0 +1,801 @@
+//! Mustache rendering for template repos, following the mustache spec: the core modules,
+//! lambdas and inheritance (`{{<parent}}` with `{{$block}}` overrides). The `mustache`
+//! crate always HTML-escapes `{{name}}`, which puts `&amp;` into vim and JSON configs, so
+//! escaping here is chosen per template. Data is the crate's `mustache::Data`, so
+//! contexts built with `MapBuilder` render unchanged; `Data::Fun` values are lambdas.
+//! Parsed files also answer what they include and interpolate, so templates can be
+//! vetted and checked against a context without a second parser.
+
+use mustache::Data;
+use std::collections::{BTreeSet, HashMap};
+
+/// Partials, parents and lambdas nested deeper than this are an error rather than a
+/// stack overflow.
+const MAX_DEPTH: usize = 100;
+
+/// How `{{name}}` values are escaped. `{{{name}}}` and `{{&name}}` are never escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// Values as they are, for plain config files.
+    None,
+    /// The spec's HTML escaping.
+    Html,
+    /// For values inside JSON strings.
+    Json,
+    Xml,
+    /// For values inside double-quoted shell strings.
+    Shell,
+}
+
+impl Escape {
+    pub fn parse(name: &str) -> Option<Escape> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Escape::None),
+            "html" => Some(Escape::Html),
+            "json" => Some(Escape::Json),
+            "xml" => Some(Escape::Xml),
+            "shell" => Some(Escape::Shell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Escape::None => "none",
+            Escape::Html => "html",
+            Escape::Json => "json",
+            Escape::Xml => "xml",
+            Escape::Shell => "shell",
+        }
+    }
+
+    /// The mode for a rendered file, by extension.
+    pub fn for_filename(filename: &str) -> Escape {
+        let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "json" | "jsonc" => Escape::Json,
+            "xml" | "plist" | "tmtheme" | "xaml" | "svg" | "html" => Escape::Xml,
+            "sh" | "bash" | "zsh" => Escape::Shell,
+            _ => Escape::None,
+        }
+    }
+
+    fn push(&self, out: &mut String, value: &str) {
+        for c in value.chars() {
+            match (self, c) {
+                (Escape::Html | Escape::Xml, '&') => out.push_str("&amp;"),
+                (Escape::Html | Escape::Xml, '<') => out.push_str("&lt;"),
+                (Escape::Html | Escape::Xml, '>') => out.push_str("&gt;"),
+                (Escape::Html | Escape::Xml, '"') => out.push_str("&quot;"),
+                (Escape::Xml, '\'') => out.push_str("&apos;"),
+                (Escape::Json, '"' | '\\') | (Escape::Shell, '"' | '\\' | '$' | '`') => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                (Escape::Json, '\n') => out.push_str("\\n"),
+                (Escape::Json, '\r') => out.push_str("\\r"),
+                (Escape::Json, '\t') => out.push_str("\\t"),
+                (Escape::Json, c) if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+                (_, c) => out.push(c),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Variable { name: String, escaped: bool },
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+        /// Source between the tags, which is what a lambda section receives.
+        raw: String,
+        delimiters: (String, String),
+    },
+    /// `indent` is the whitespace before a standalone tag, added to every line.
+    Partial { name: String, indent: String, line: usize },
+    /// `{{<name}}`; only the blocks among `children` matter.
+    Parent { name: String, indent: String, line: usize, children: Vec<Node> },
+    /// `indent` is stripped from the block's lines where it's defined and added where
+    /// it's rendered. `own_lines` marks a block whose tags stand on lines of their own.
+    Block { name: String, indent: String, children: Vec<Node>, own_lines: bool },
+}
+
+/// A structural error and the 1-based line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// One template file, parsed but not yet joined to the partials it includes.
+#[derive(Debug)]
+pub struct Parsed {
+    nodes: Vec<Node>,
+}
+
+impl Parsed {
+    pub fn parse(source: &str) -> Result<Parsed, Problem> {
+        Ok(Parsed { nodes: parse(source, default_delimiters())? })
+    }
+
+    /// The partials and parents this file names, with the line of each tag.
+    pub fn includes(&self) -> Vec<(&str, usize)> {
+        let mut includes = Vec::new();
+        referenced(&self.nodes, &mut includes);
+        includes
+    }
+}
+
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+    partials: HashMap<String, Vec<Node>>,
+}
+
+impl Template {
+    /// A template from files already parsed, with its partials and parents by name.
+    pub fn link(main: Parsed, partials: HashMap<String, Parsed>) -> Template {
+        let partials = partials.into_iter().map(|(name, parsed)| (name, parsed.nodes)).collect();
+        Template { nodes: main.nodes, partials }
+    }
+
+    /// Names the template and its partials interpolate, without the implicit iterator `.`.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        for nodes in std::iter::once(&self.nodes).chain(self.partials.values()) {
+            interpolated(nodes, &mut variables);
+        }
+        variables
+    }
+
+    pub fn render(&self, data: &Data, escape: Escape) -> Result<String, String> {
+        let mut renderer = Renderer {
+            template: self,
+            escape,
+            out: String::new(),
+            indent: String::new(),
+            line_start: true,
+            depth: 0,
+        };
+        renderer.render(&self.nodes, &mut vec![data], &HashMap::new())?;
+        Ok(renderer.out)
+    }
+}
+
+fn default_delimiters() -> (String, String) {
+    ("{{".to_string(), "}}".to_string())
+}
+
+/// Names of the partials and parents `nodes` include, and the lines of their tags.
+fn referenced<'n>(nodes: &'n [Node], names: &mut Vec<(&'n str, usize)>) {
+    for node in nodes {
+        match node {
+            Node::Partial { name, line, .. } => names.push((name, *line)),
+            Node::Parent { name, line, children, .. } => {
+                names.push((name, *line));
+                referenced(children, names);
+            }
+            Node::Section { children, .. } | Node::Block { children, .. } => referenced(children, names),
+            Node::Text(_) | Node::Variable { .. } => {}
+        }
+    }
+}
+
+fn interpolated(nodes: &[Node], names: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::Variable { name, .. } if name != "." => {
+                names.insert(name.clone());
+            }
+            Node::Section { children, .. } | Node::Parent { children, .. } | Node::Block { children, .. } => interpolated(children, names),
+            Node::Text(_) | Node::Variable { .. } | Node::Partial { .. } => {}
+        }
+    }
+}
+
+/// Variables that `has` can't resolve. Dotted names resolve by their first segment.
+pub fn missing(variables: &BTreeSet<String>, has: impl Fn(&str) -> bool) -> Vec<&str> {
+    variables.iter()
+        .map(String::as_str)
+        .filter(|name| !has(name.split('.').next().unwrap_or(name)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sigil {
+    Variable,
+    Unescaped,
+    Section,
+    Inverted,
+    Close,
+    Partial,
+    Parent,
+    Block,
+    Comment,
+    Delimiters,
+}
+
+impl Sigil {
+    /// Tags that leave no output of their own, so a line holding only them and
+    /// whitespace is removed entirely.
+    fn can_stand_alone(&self) -> bool {
+        !matches!(self, Sigil::Variable | Sigil::Unescaped)
+    }
+}
+
+#[derive(Debug)]
+struct Tag {
+    sigil: Sigil,
+    name: String,
+    line: usize,
+    start: usize,
+    end: usize,
+    delimiters: (String, String),
+    /// The line's leading whitespace, when the tag is on a standalone line.
+    indent: Option<String>,
+}
+
+#[derive(Debug)]
+enum Token<'s> {
+    /// Text up to and including a newline, or up to a tag. `line_start` marks text that
+    /// begins a line.
+    Text { text: &'s str, line_start: bool },
+    Tag(Tag),
+}
+
+fn parse(source: &str, delimiters: (String, String)) -> Result<Vec<Node>, Problem> {
+    let tokens = standalone(tokenize(source, delimiters)?);
+    build(source, tokens)
+}
+
+fn tokenize(source: &str, (mut open, mut close): (String, String)) -> Result<Vec<Token<'_>>, Problem> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = source[pos..].find(&open).map(|i| pos + i) {
+        push_text(&mut tokens, &source[pos..start]);
+        let line = source[..start].matches('\n').count() + 1;
+        let inner_start = start + open.len();
+        let rest = &source[inner_start..];
+        let (sigil, inner, end) = if let Some(rest) = rest.strip_prefix('{') {
+            let closing = format!("}}{}", close);
+            let Some(i) = rest.find(&closing) else {
+                return Err(Problem { line, message: format!("unbalanced unescape tag, expected '{}'", closing) });
+            };
+            (Sigil::Unescaped, rest[..i].trim(), inner_start + 1 + i + closing.len())
+        } else if let Some(rest) = rest.strip_prefix('=') {
+            let closing = format!("={}", close);
+            let Some(i) = rest.find(&closing) else {
+                return Err(Problem { line, message: "invalid set delimiter tag".to_string() });
+            };
+            (Sigil::Delimiters, rest[..i].trim(), inner_start + 1 + i + closing.len())
+        } else {
+            let Some(i) = rest.find(&close) else {
+                return Err(Problem { line, message: format!("unclosed tag, expected '{}'", close) });
+            };
+            let inner = rest[..i].trim();
+            let sigil = match inner.chars().next() {
+                Some('#') => Sigil::Section,
+                Some('^') => Sigil::Inverted,
+                Some('/') => Sigil::Close,
+                Some('>') => Sigil::Partial,
+                Some('<') => Sigil::Parent,
+                Some('$') => Sigil::Block,
+                Some('&') => Sigil::Unescaped,
+                Some('!') => Sigil::Comment,
+                _ => Sigil::Variable,
+            };
+            let name = match sigil {
+                Sigil::Variable => inner,
+                Sigil::Comment => "",
+                _ => inner[1..].trim(),
+            };
+            (sigil, name, inner_start + i + close.len())
+        };
+
+        let delimiters = (open.clone(), close.clone());
+        match sigil {
+            Sigil::Delimiters => {
+                let mut parts = inner.split_whitespace();
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(o), Some(c), None) => {
+                        open = o.to_string();
+                        close = c.to_string();
+                    }
+                    _ => return Err(Problem { line, message: "invalid set delimiter tag".to_string() }),
+                }
+            }
+            Sigil::Comment => {}
+            _ if inner.is_empty() => return Err(Problem { line, message: "empty tag".to_string() }),
+            _ => {}
+        }
+        let name = if sigil == Sigil::Delimiters { String::new() } else { inner.to_string() };
+        tokens.push(Token::Tag(Tag { sigil, name, line, start, end, delimiters, indent: None }));
+        pos = end;
+    }
+    push_text(&mut tokens, &source[pos..]);
+    Ok(tokens)
+}
+
+fn push_text<'s>(tokens: &mut Vec<Token<'s>>, text: &'s str) {
+    for piece in text.split_inclusive('\n') {
+        tokens.push(Token::Text { text: piece, line_start: false });
+    }
+}
+
+/// Drop the whitespace and newline of lines that hold only standalone tags, recording
+/// the indentation on the tags, and mark the text that starts each remaining line.
+fn standalone(tokens: Vec<Token<'_>>) -> Vec<Token<'_>> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut line = Vec::new();
+    for token in tokens {
+        let ends_line = matches!(&token, Token::Text { text, .. } if text.ends_with('\n'));
+        line.push(token);
+        if ends_line {
+            flush_line(&mut line, &mut out);
+        }
+    }
+    flush_line(&mut line, &mut out);
+    out
+}
+
+fn flush_line<'s>(line: &mut Vec<Token<'s>>, out: &mut Vec<Token<'s>>) {
+    let is_blank = |text: &str| text.trim_matches([' ', '\t', '\r', '\n']).is_empty();
+    let has_tag = line.iter().any(|token| matches!(token, Token::Tag(_)));
+    let standalone = has_tag && line.iter().all(|token| match token {
+        Token::Text { text, .. } => is_blank(text),
+        Token::Tag(tag) => tag.sigil.can_stand_alone(),
+    });
+
+    if standalone {
+        let indent = match line.first() {
+            Some(Token::Text { text, .. }) if !text.ends_with('\n') => text.to_string(),
+            _ => String::new(),
+        };
+        for token in line.drain(..) {
+            if let Token::Tag(mut tag) = token {
+                tag.indent = Some(indent.clone());
+                out.push(Token::Tag(tag));
+            }
+        }
+    } else {
+        if let Some(Token::Text { line_start, .. }) = line.first_mut() {
+            *line_start = true;
+        }
+        out.append(line);
+    }
+}
+
+/// Nest the tokens into sections. A block opened on a standalone line is indented like
+/// the line after it, or like its own line when it closes there too; its text loses that
+/// indentation, which is added back wherever the block is rendered.
+fn build(source: &str, tokens: Vec<Token<'_>>) -> Result<Vec<Node>, Problem> {
+    let mut open: Vec<(Tag, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+
+    for token in tokens {
+        let tag = match token {
+            Token::Text { text, line_start } => {
+                let text = match (line_start, block_indent(&open)) {
+                    (true, Some(indent)) => text.strip_prefix(indent.as_str()).unwrap_or(text),
+                    _ => text,
+                };
+                nodes.push(Node::Text(text.to_string()));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        // A standalone tag's indentation is relative to the block it's in, like its text
+        let mut tag = tag;
+        let enclosing = match tag.sigil {
+            Sigil::Close => &open[..open.len().saturating_sub(1)],
+            _ => &open[..],
+        };
+        if let (Some(indent), Some(outer)) = (tag.indent.as_mut(), block_indent(enclosing))
+            && let Some(relative) = indent.strip_prefix(outer.as_str()) {
+            *indent = relative.to_string();
+        }
+        match tag.sigil {
+            Sigil::Variable | Sigil::Unescaped => {
+                nodes.push(Node::Variable { name: tag.name, escaped: tag.sigil == Sigil::Variable });
+            }
+            Sigil::Partial => nodes.push(Node::Partial { name: tag.name, indent: tag.indent.unwrap_or_default(), line: tag.line }),
+            Sigil::Section | Sigil::Inverted | Sigil::Parent | Sigil::Block => {
+                if tag.sigil == Sigil::Block && tag.indent.is_some() {
+                    let next_line = source[tag.end..].split_once('\n').map_or("", |(_, rest)| rest);
+                    let content = next_line.trim_start_matches([' ', '\t']);
+                    let indent = &next_line[..next_line.len() - content.len()];
+                    let outer = block_indent(&open).unwrap_or_default();
+                    tag.indent = Some(indent.strip_prefix(outer.as_str()).unwrap_or(indent).to_string());
+                }
+                open.push((tag, std::mem::take(&mut nodes)));
+            }
+            Sigil::Close => {
+                let Some((opened, parent_nodes)) = open.pop() else {
+                    return Err(Problem { line: tag.line, message: format!("closing tag for unopened section '{}'", tag.name) });
+                };
+                if opened.name != tag.name {
+                    return Err(Problem {
+                        line: tag.line,
+                        message: format!("'{}' closes section '{}' opened on line {}", tag.name, opened.name, opened.line),
+                    });
+                }
+                let children = std::mem::replace(&mut nodes, parent_nodes);
+                let own_lines = opened.indent.is_some() && tag.indent.is_some();
+                let indent = match opened.line == tag.line {
+                    true if opened.sigil == Sigil::Block => tag.indent.unwrap_or_default(),
+                    _ => opened.indent.unwrap_or_default(),
+                };
+                nodes.push(match opened.sigil {
+                    Sigil::Parent => Node::Parent { name: opened.name, indent, line: opened.line, children },
+                    Sigil::Block => Node::Block { name: opened.name, indent, children, own_lines },
+                    _ => Node::Section {
+                        name: opened.name,
+                        inverted: opened.sigil == Sigil::Inverted,
+                        children,
+                        raw: source[opened.end..tag.start].to_string(),
+                        delimiters: opened.delimiters,
+                    },
+                });
+            }
+            Sigil::Comment | Sigil::Delimiters => {}
+        }
+    }
+
+    match open.pop() {
+        Some((tag, _)) => Err(Problem { line: tag.line, message: format!("unclosed section '{}'", tag.name) }),
+        None => Ok(nodes),
+    }
+}
+
+/// The indentation the open blocks add up to, each relative to the one around it, or
+/// `None` outside blocks opened on standalone lines.
+fn block_indent(open: &[(Tag, Vec<Node>)]) -> Option<String> {
+    open.iter()
+        .filter_map(|(tag, _)| tag.indent.as_deref().filter(|_| tag.sigil == Sigil::Block))
+        .fold(None, |total: Option<String>, indent| Some(total.unwrap_or_default() + indent))
+}
+
+/// Block overrides in effect, by name.
+type Blocks<'a> = HashMap<&'a str, &'a [Node]>;
+
+struct Renderer<'t> {
+    template: &'t Template,
+    escape: Escape,
+    out: String,
+    /// Added to each line, from standalone partials, parents and blocks.
+    indent: String,
+    line_start: bool,
+    depth: usize,
+}
+
+impl<'t> Renderer<'t> {
+    fn render<'a, 'd>(&mut self, nodes: &'a [Node], stack: &mut Vec<&'d Data>, blocks: &Blocks<'a>) -> Result<(), String>
+    where
+        't: 'a,
+    {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.write_text(text),
+                Node::Variable { name, escaped } => match lookup(stack, name) {
+                    Some(Data::Fun(lambda)) => {
+                        let source = call(lambda, String::new())?;
+                        let nodes = self.parse_lambda(&source, default_delimiters())?;
+                        let saved = (std::mem::take(&mut self.out), self.line_start);
+                        self.nested(|renderer| renderer.render(&nodes, stack, blocks))?;
+                        let rendered = std::mem::replace(&mut self.out, saved.0);
+                        self.line_start = saved.1;
+                        self.write_value(&rendered, *escaped);
+                    }
+                    Some(Data::String(value)) => self.write_value(value, *escaped),
+                    Some(Data::Bool(value)) => self.write_value(if *value { "true" } else { "false" }, *escaped),
+                    _ => {}
+                },
+                Node::Section { name, inverted, children, raw, delimiters } => {
+                    let value = lookup(stack, name);
+                    if *inverted {
+                        if !truthy(value) {
+                            self.render(children, stack, blocks)?;
+                        }
+                        continue;
+                    }
+                    match value {
+                        Some(Data::Fun(lambda)) => {
+                            let source = call(lambda, raw.clone())?;
+                            let nodes = self.parse_lambda(&source, delimiters.clone())?;
+                            self.nested(|renderer| renderer.render(&nodes, stack, blocks))?;
+                        }
+                        Some(Data::Vec(items)) => {
+                            for item in items {
+                                stack.push(item);
+                                let result = self.render(children, stack, blocks);
+                                stack.pop();
+                                result?;
+                            }
+                        }
+                        Some(Data::Bool(true)) => self.render(children, stack, blocks)?,
+                        Some(value) if truthy(Some(value)) => {
+                            stack.push(value);
+                            let result = self.render(children, stack, blocks);
+                            stack.pop();
+                            result?;
+                        }
+                        _ => {}
+                    }
+                }
+                Node::Partial { name, indent, .. } => {
+                    if let Some(partial) = self.template.partials.get(name) {
+                        self.indented(indent, |renderer| renderer.render(partial, stack, blocks))?;
+                    }
+                }
+                Node::Parent { name, indent, children, .. } => {
+                    // The outermost override of a block wins
+                    let mut overrides: Blocks<'a> = children.iter()
+                        .filter_map(|child| match child {
+                            Node::Block { name, children, .. } => Some((name.as_str(), children.as_slice())),
+                            _ => None,
+                        })
+                        .collect();
+                    overrides.extend(blocks.iter().map(|(name, nodes)| (*name, *nodes)));
+                    if let Some(parent) = self.template.partials.get(name) {
+                        self.indented(indent, |renderer| renderer.render(parent, stack, &overrides))?;
+                    }
+                }
+                Node::Block { name, indent, children, own_lines } => {
+                    let content = blocks.get(name.as_str()).copied().unwrap_or(children);
+                    let start = self.out.len();
+                    self.indented(indent, |renderer| renderer.render(content, stack, blocks))?;
+                    // Content that took the place of whole lines ends its last one
+                    if *own_lines && self.out.len() > start && !self.line_start {
+                        self.out.push('\n');
+                        self.line_start = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_lambda(&self, source: &str, delimiters: (String, String)) -> Result<Vec<Node>, String> {
+        parse(source, delimiters).map_err(|p| format!("lambda result line {}: {}", p.line, p.message))
+    }
+
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("partials nested more than {} deep", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn indented(&mut self, indent: &str, f: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        let outer = self.indent.len();
+        self.indent.push_str(indent);
+        let result = self.nested(f);
+        self.indent.truncate(outer);
+        result
+    }
+
+    fn write_text(&mut self, text: &str) {
+        for piece in text.split_inclusive('\n') {
+            if self.line_start && !matches!(piece, "\n" | "\r\n") {
+                self.out.push_str(&self.indent);
+            }
+            self.out.push_str(piece);
+            self.line_start = piece.ends_with('\n');
+        }
+    }
+
+    /// Interpolated values are indented where they start a line, but their own newlines
+    /// aren't.
+    fn write_value(&mut self, value: &str, escaped: bool) {
+        if value.is_empty() {
+            return;
+        }
+        if self.line_start {
+            self.out.push_str(&self.indent);
+        }
+        match escaped {
+            true => self.escape.push(&mut self.out, value),
+            false => self.out.push_str(value),
+        }
+        self.line_start = false;
+    }
+}
+
+fn call(lambda: &std::cell::RefCell<Box<dyn FnMut(String) -> String + Send>>, text: String) -> Result<String, String> {
+    let mut lambda = lambda.try_borrow_mut().map_err(|_| "lambda called itself".to_string())?;
+    Ok(lambda(text))
+}
+
+/// `name` in the innermost context that has its first segment, then down the rest.
+fn lookup<'d>(stack: &[&'d Data], name: &str) -> Option<&'d Data> {
+    if name == "." {
+        return stack.last().copied();
+    }
+    let mut parts = name.split('.');
+    let first = parts.next()?;
+    let mut value = stack.iter().rev().find_map(|data| match data {
+        Data::Map(map) => map.get(first),
+        _ => None,
+    })?;
+    for part in parts {
+        value = match value {
+            Data::Map(map) => map.get(part)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn truthy(value: Option<&Data>) -> bool {
+    match value {
+        None | Some(Data::Null) | Some(Data::Bool(false)) => false,
+        Some(Data::Vec(items)) => !items.is_empty(),
+        Some(Data::String(value)) => !value.is_empty(),
+        Some(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// `source` linked with every partial and parent `load` finds for it.
+    fn compile(source: &str, load: impl Fn(&str) -> Option<String>) -> Result<Template, Problem> {
+        let main = Parsed::parse(source)?;
+        let mut partials = HashMap::new();
+        let mut pending: Vec<String> = main.includes().into_iter().map(|(name, _)| name.to_string()).collect();
+        while let Some(name) = pending.pop() {
+            if partials.contains_key(&name) {
+                continue;
+            }
+            let Some(partial_source) = load(&name) else { continue };
+            let partial = Parsed::parse(&partial_source)?;
+            pending.extend(partial.includes().into_iter().map(|(name, _)| name.to_string()));
+            partials.insert(name, partial);
+        }
+        Ok(Template::link(main, partials))
+    }
+
+    #[test]
+    fn test_includes_and_variables() {
+        let source = "{{! comment }}\n{{scheme-name}} {{{base00-hex}}}\n{{#scheme-is-dark-variant}}{{& base10-hex }}{{/scheme-is-dark-variant}}\n\
+            {{=<% %>=}}<% base01-hex %><%# items %><% . %><%/ items %>\n<%> footer %>\n<%< page %><%$ body %><% body-text %><%/ body %><%/ page %>";
+        let parsed = Parsed::parse(source).unwrap();
+        assert_eq!(parsed.includes(), [("footer", 5), ("page", 6)]);
+        let template = compile(source, |name| (name == "footer").then(|| "{{footer-text}}".to_string())).unwrap();
+        let variables = template.variables();
+        assert_eq!(variables.iter().collect::<Vec<_>>(), ["base00-hex", "base01-hex", "base10-hex", "body-text", "footer-text", "scheme-name"]);
+        assert_eq!(missing(&variables, |name| !name.starts_with("base1")), ["base10-hex"]);
+    }
+
+    /// The spec's data as `Data`. Numbers interpolate as their JSON text.
+    fn data(value: &serde_json::Value) -> Data {
+        match value {
+            serde_json::Value::Null => Data::Null,
+            serde_json::Value::Bool(b) => Data::Bool(*b),
+            serde_json::Value::Number(n) => Data::String(n.to_string()),
+            serde_json::Value::String(s) => Data::String(s.clone()),
+            serde_json::Value::Array(items) => Data::Vec(items.iter().map(data).collect()),
+            serde_json::Value::Object(map) => Data::Map(map.iter().map(|(k, v)| (k.clone(), data(v))).collect()),
+        }
+    }
+
+    /// The spec ships lambdas as code in other languages; these are the same lambdas.
+    fn lambda(test: &str) -> Data {
+        let mut calls = 0;
+        let f: Box<dyn FnMut(String) -> String + Send> = match test {
+            "Interpolation" => Box::new(|_| "world".to_string()),
+            "Interpolation - Expansion" => Box::new(|_| "{{planet}}".to_string()),
+            "Interpolation - Alternate Delimiters" => Box::new(|_| "|planet| => {{planet}}".to_string()),
+            "Interpolation - Multiple Calls" => Box::new(move |_| {
+                calls += 1;
+                calls.to_string()
+            }),
+            "Escaping" => Box::new(|_| ">".to_string()),
+            "Section" => Box::new(|text| if text == "{{x}}" { "yes" } else { "no" }.to_string()),
+            "Section - Expansion" => Box::new(|text| format!("{}{{{{planet}}}}{}", text, text)),
+            "Section - Alternate Delimiters" => Box::new(|text| format!("{}{{{{planet}}}} => |planet|{}", text, text)),
+            "Section - Multiple Calls" => Box::new(|text| format!("__{}__", text)),
+            "Inverted Section" => Box::new(|_| String::new()),
+            _ => panic!("no lambda for '{}'", test),
+        };
+        Data::Fun(RefCell::new(f))
+    }
+
+    /// The number of tests in a spec file, and descriptions of the failing ones.
+    fn run_spec(file: &str, json: &str) -> (usize, Vec<String>) {
+        let spec: serde_json::Value = serde_json::from_str(json).unwrap();
+        let tests = spec["tests"].as_array().unwrap();
+        let mut failures = Vec::new();
+        for test in tests {
+            let name = test["name"].as_str().unwrap();
+            let mut context = data(&test["data"]);
+            if let (Data::Map(map), Some(_)) = (&mut context, test["data"].get("lambda")) {
+                map.insert("lambda".to_string(), lambda(name));
+            }
+            let partials = test.get("partials").and_then(|p| p.as_object()).cloned().unwrap_or_default();
+            let rendered = compile(test["template"].as_str().unwrap(), |name| {
+                partials.get(name).and_then(|p| p.as_str()).map(String::from)
+            })
+            .map_err(|p| p.message)
+            .and_then(|template| template.render(&context, Escape::Html));
+            let expected = test["expected"].as_str().unwrap();
+            if rendered.as_deref() != Ok(expected) {
+                failures.push(format!("{} / {}: expected {:?}, got {:?}", file, name, expected, rendered));
+            }
+        }
+        (tests.len(), failures)
+    }
+
+    #[test]
+    fn test_mustache_spec() {
+        let specs = [
+            ("comments", include_str!("../tests/fixtures/mustache-spec/comments.json")),
+            ("delimiters", include_str!("../tests/fixtures/mustache-spec/delimiters.json")),
+            ("interpolation", include_str!("../tests/fixtures/mustache-spec/interpolation.json")),
+            ("inverted", include_str!("../tests/fixtures/mustache-spec/inverted.json")),
+            ("partials", include_str!("../tests/fixtures/mustache-spec/partials.json")),
+            ("sections", include_str!("../tests/fixtures/mustache-spec/sections.json")),
+            ("~lambdas", include_str!("../tests/fixtures/mustache-spec/~lambdas.json")),
+            ("~inheritance", include_str!("../tests/fixtures/mustache-spec/~inheritance.json")),
+        ];
+        let results: Vec<_> = specs.iter().map(|(file, json)| run_spec(file, json)).collect();
+        let failures: Vec<String> = results.iter().flat_map(|(_, failures)| failures.clone()).collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+        assert_eq!(results.iter().map(|(count, _)| count).sum::<usize>(), 173);
+    }
+
+    #[test]
+    fn test_escape_modes() {
+        let template = compile("{{name}}|{{{name}}}", |_| None).unwrap();
+        let data = mustache::MapBuilder::new().insert_str("name", "Tom & \"Jerry's\" $x\n").build();
+        let render = |escape| template.render(&data, escape).unwrap();
+        assert_eq!(render(Escape::None), "Tom & \"Jerry's\" $x\n|Tom & \"Jerry's\" $x\n");
+        assert!(render(Escape::Html).starts_with("Tom &amp; &quot;Jerry's&quot; $x\n|Tom & "));
+        assert!(render(Escape::Xml).starts_with("Tom &amp; &quot;Jerry&apos;s&quot; $x\n|"));
+        assert!(render(Escape::Json).starts_with("Tom & \\\"Jerry's\\\" $x\\n|"));
+        assert!(render(Escape::Shell).starts_with("Tom & \\\"Jerry's\\\" \\$x\n|"));
+
+        assert_eq!(Escape::for_filename("colors/base16-monokai.vim"), Escape::None);
+        assert_eq!(Escape::for_filename("theme.JSON"), Escape::Json);
+        assert_eq!(Escape::for_filename("monokai.tmTheme"), Escape::Xml);
+        assert_eq!(Escape::for_filename("scripts/base16-monokai.sh"), Escape::Shell);
+        assert_eq!(Escape::parse("Shell"), Some(Escape::Shell));
+        assert_eq!(Escape::parse("url"), None);
+    }
+
+    #[test]
+    fn test_nested_block_defaults_keep_their_indentation() {
+        let partials = HashMap::from([
+            ("parent", "{{<grandparent}}{{$block}}\n  one\n  {{$nested}}\n    two\n  {{/nested}}\n  {{>item}}\n{{/block}}{{/grandparent}}\n"),
+            ("grandparent", "  {{$block}}{{/block}}\n"),
+            ("item", "three\n"),
+        ]);
+        let template = compile("{{<parent}}{{/parent}}", |name| partials.get(name).map(|p| p.to_string())).unwrap();
+        assert_eq!(template.render(&Data::Map(HashMap::new()), Escape::None).unwrap(), "  one\n    two\n  three\n");
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let problem = |source: &str| compile(source, |_| None).unwrap_err();
+        assert_eq!(problem("a\n{{#dark}}\n"), Problem { line: 2, message: "unclosed section 'dark'".to_string() });
+        assert_eq!(problem("a\n\n{{/dark}}").line, 3);
+        assert_eq!(problem("{{#a}}\n{{#b}}\n{{/a}}").message, "'a' closes section 'b' opened on line 2");
+        assert_eq!(problem("ok\n{{base00-hex").message, "unclosed tag, expected '}}'");
+        assert_eq!(problem("{{{base00-hex}}").line, 1);
+        assert_eq!(problem("x\n{{ }}").message, "empty tag");
+        assert_eq!(problem("{{<parent}}\n{{$block}}\n{{/parent}}").message, "'parent' closes section 'block' opened on line 2");
+        let partial = compile("{{>broken}}", |_| Some("\n{{#a}}".to_string())).unwrap_err();
+        assert_eq!(partial.line, 2);
+        let recursive = compile("{{>loop}}", |_| Some("x{{>loop}}".to_string())).unwrap();
+        assert!(recursive.render(&Data::Map(HashMap::new()), Escape::None).unwrap_err().contains("nested more than"));
+    }
+}
@@ -0,0 +1,218 @@
+//! Name search shared by schemes and templates: a trigram index answering exact,
+//! prefix, substring and typo queries without comparing the query to every name.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Lowest Jaro-Winkler similarity `search` reports as a typo match.
+const FUZZY_FLOOR: f64 = 0.7;
+
+type Trigram = [char; 3];
+
+/// How a name matched a query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Substring,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hit<'a> {
+    pub name: &'a str,
+    pub kind: MatchKind,
+    /// Share of the name covered by the query, or similarity for typo matches.
+    pub score: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct NameIndex {
+    /// Lowercase names, sorted.
+    names: Vec<String>,
+    /// Padded trigram -> positions in `names`. Padding marks word start and end, so
+    /// the same postings serve substring lookups and typo ranking.
+    postings: HashMap<Trigram, Vec<u32>>,
+}
+
+impl NameIndex {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a String>) -> Self {
+        let mut names: Vec<String> = names.into_iter().map(|n| n.to_lowercase()).collect();
+        names.sort();
+        names.dedup();
+        let mut postings: HashMap<Trigram, Vec<u32>> = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            let mut grams = trigrams(&padded(name));
+            grams.sort();
+            grams.dedup();
+            for gram in grams {
+                postings.entry(gram).or_default().push(i as u32);
+            }
+        }
+        NameIndex { names, postings }
+    }
+
+    /// Names similar to `query` by Jaro-Winkler, best first. Names sharing enough
+    /// trigrams with the query to be a few edits away are scored; how many they share
+    /// doesn't decide the ranking.
+    pub fn similar(&self, query: &str) -> Vec<(&str, f64)> {
+        let query = query.to_lowercase();
+        let mut scored: Vec<(&str, f64)> = self.candidates(&query).into_iter()
+            .map(|id| {
+                let name = self.names[id as usize].as_str();
+                (name, strsim::jaro_winkler(&query, name))
+            })
+            .collect();
+        scored.sort_by(|(a, sa), (b, sb)| sb.partial_cmp(sa).unwrap().then(a.cmp(b)));
+        scored
+    }
+
+    /// Names sharing at least `min_shared` of the query's padded trigrams.
+    fn candidates(&self, query: &str) -> Vec<u32> {
+        let mut grams = trigrams(&padded(query));
+        grams.sort();
+        grams.dedup();
+        let mut ids: Vec<u32> = grams.iter()
+            .flat_map(|gram| self.postings.get(gram).into_iter().flatten().copied())
+            .collect();
+        ids.sort_unstable();
+        let needed = min_shared(query.chars().count());
+        ids.chunk_by(|a, b| a == b)
+            .filter(|run| run.len() >= needed)
+            .map(|run| run[0])
+            .collect()
+    }
+
+    /// Exact, prefix and substring matches, then typo matches, best first.
+    pub fn search(&self, query: &str) -> Vec<Hit<'_>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<Hit> = self.containing(&query)
+            .into_iter()
+            .map(|name| Hit {
+                name,
+                kind: match name {
+                    n if n == query => MatchKind::Exact,
+                    n if n.starts_with(&query) => MatchKind::Prefix,
+                    _ => MatchKind::Substring,
+                },
+                score: query.chars().count() as f64 / name.chars().count() as f64,
+            })
+            .collect();
+        let fuzzy: Vec<Hit> = self.similar(&query).into_iter()
+            .filter(|(name, score)| *score >= FUZZY_FLOOR && !name.contains(&query))
+            .map(|(name, score)| Hit { name, kind: MatchKind::Fuzzy, score })
+            .collect();
+        hits.extend(fuzzy);
+
+        hits.sort_by(|a, b| a.kind.cmp(&b.kind)
+            .then(b.score.partial_cmp(&a.score).unwrap())
+            .then(a.name.cmp(b.name)));
+        hits
+    }
+
+    /// Names containing `query`, narrowed by the query's rarest trigram.
+    fn containing(&self, query: &str) -> Vec<&str> {
+        let grams = trigrams(query);
+        if grams.is_empty() {
+            // Too short to have trigrams; there are few enough names to check them all
+            return self.names.iter().map(String::as_str).filter(|n| n.contains(query)).collect();
+        }
+        let mut postings = Vec::with_capacity(grams.len());
+        for gram in &grams {
+            match self.postings.get(gram) {
+                Some(ids) => postings.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        let Some(rarest) = postings.into_iter().min_by_key(|ids| ids.len()) else {
+            return Vec::new();
+        };
+        rarest.iter()
+            .map(|id| self.names[*id as usize].as_str())
+            .filter(|n| n.contains(query))
+            .collect()
+    }
+}
+
+/// Shared padded trigrams a name needs to be scored against a query of `len`
+/// characters: the query has `len + 1`, a query allows one edit per four characters
+/// (at least one), and each edit, counting a swapped pair as one, breaks at most four.
+fn min_shared(len: usize) -> usize {
+    let edits = (len / 4).max(1);
+    (len + 1).saturating_sub(4 * edits).max(1)
+}
+
+fn padded(name: &str) -> String {
+    format!("  {} ", name)
+}
+
+fn trigrams(text: &str) -> Vec<Trigram> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(names: &[&str]) -> NameIndex {
+        let names: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        NameIndex::new(&names)
+    }
+
+    #[test]
+    fn test_search_ranks_exact_prefix_substring_then_typos() {
+        let index = index(&["one-dark", "onedark-pro", "atom-one-dark", "monokai", "gruvbox-dark-hard"]);
+        let hits: Vec<(&str, MatchKind)> = index.search("one-dark").iter().map(|h| (h.name, h.kind)).collect();
+        assert_eq!(hits[0], ("one-dark", MatchKind::Exact));
+        assert_eq!(hits[1], ("atom-one-dark", MatchKind::Substring));
+        assert!(hits[2..].iter().all(|(_, kind)| *kind == MatchKind::Fuzzy));
+        assert!(hits.iter().any(|(name, _)| *name == "onedark-pro"));
+
+        let hits = index.search("Mono");
+        assert_eq!((hits[0].name, hits[0].kind), ("monokai", MatchKind::Prefix));
+        assert_eq!(index.search("ok")[0].name, "monokai");
+        assert!(index.search("").is_empty());
+        assert!(index.search("zzzzzz").is_empty());
+    }
+
+    #[test]
+    fn test_similar_finds_typos() {
+        let index = index(&["monokai", "dracula", "solarized-light"]);
+        let similar = index.similar("monoki");
+        assert_eq!(similar[0].0, "monokai");
+        assert!(similar[0].1 > 0.9);
+        assert_eq!(index.similar("drcaula")[0].0, "dracula");
+    }
+
+    #[test]
+    fn test_similar_matches_exhaustive_scan() {
+        // Long names containing the query share more trigrams with it than the swapped
+        // pair does, but score lower; the best match must not depend on trigram counts
+        let mut names: Vec<String> = (0..100).map(|i| format!("q{:02}abcdefgh-ijkl-with-a-long-suffix", i)).collect();
+        names.push("bacdefgh-ijkl".to_string());
+        let index = NameIndex::new(&names);
+        let exhaustive = names.iter()
+            .map(|name| (name.as_str(), strsim::jaro_winkler("abcdefgh-ijkl", name)))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+        assert_eq!(exhaustive.0, "bacdefgh-ijkl");
+        assert_eq!(index.similar("abcdefgh-ijkl")[0], exhaustive);
+    }
+
+    #[test]
+    fn test_similar_scores_only_near_names() {
+        // Every name shares the query's leading trigram; few share enough to be scored
+        let mut names: Vec<String> = (0..5000).map(|i| format!("m{}-{}", ["ocha", "aterial", "elange", "ountain"][i % 4], i)).collect();
+        names.push("monokai".to_string());
+        let index = NameIndex::new(&names);
+        let candidates = index.candidates("monoki");
+        assert!(candidates.len() < 10, "{} candidates", candidates.len());
+        assert_eq!(index.similar("monoki")[0].0, "monokai");
+    }
+}